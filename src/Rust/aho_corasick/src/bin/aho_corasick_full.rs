@@ -0,0 +1,287 @@
+/*
+    Dense-DFA variant of the Aho-Corasick multi-pattern matcher.
+
+    `aho_corasick`'s search loop chases failure links on every input byte:
+    `while goto_fn[state][c] == FAIL { state = failure_fn[state]; }` is a
+    variable-length inner loop whose cost depends on how deep into the
+    trie a mismatch happens. This variant precomputes a complete
+    `delta[state][c]` transition matrix from `goto_fn`/`failure_fn` ahead
+    of time, so every input byte during the search is a single array
+    lookup with no failure chasing at all -- the standard NFA-to-DFA
+    trade of a larger table (`ASIZE * num_states` entries) for constant-
+    time steps.
+
+    `Set`/`Queue`/`create_new_state`/`enter_pattern`/`build_goto`/
+    `build_failure` follow the same trie/failure-function construction as
+    `aho_corasick`'s (see that crate for the full explanation), except
+    that rows here are `ASIZE` columns wide and indexed by raw byte value
+    rather than compressed to byte classes, and `build_failure` visits
+    only the `ALPHA_OFFSETS` columns. The final step -- packing a delta
+    table instead of leaving the goto/failure functions to be walked
+    directly -- is what this variant adds.
+*/
+
+use common::run::{run_multi, MultiPatternData};
+use std::env;
+use std::process::exit;
+
+const ASIZE: usize = 128;
+const FAIL: i32 = -1;
+const ALPHA_OFFSETS: &[usize] = &[65, 67, 71, 84];
+
+#[derive(Clone, Debug)]
+struct Set {
+    elements: Vec<usize>,
+}
+
+impl Set {
+    fn new() -> Set {
+        Set {
+            elements: Vec::with_capacity(8),
+        }
+    }
+
+    fn insert(&mut self, element: usize) {
+        self.elements.push(element);
+    }
+
+    fn contains(&self, element: usize) -> bool {
+        self.elements.contains(&element)
+    }
+
+    fn iter(&self) -> core::slice::Iter<'_, usize> {
+        self.elements.iter()
+    }
+
+    fn union(&mut self, other: &Set) {
+        for &element in other.elements.iter() {
+            if !self.contains(element) {
+                self.insert(element);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Queue {
+    elements: Vec<usize>,
+    head: usize,
+}
+
+impl Queue {
+    fn new() -> Queue {
+        Queue {
+            elements: Vec::with_capacity(32),
+            head: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head == self.elements.len()
+    }
+
+    fn enqueue(&mut self, element: usize) {
+        self.elements.push(element);
+    }
+
+    fn dequeue(&mut self) -> usize {
+        if self.is_empty() {
+            panic!("Queue::dequeue: underflow");
+        }
+        let value = self.elements[self.head];
+        self.head += 1;
+        value
+    }
+}
+
+fn create_new_state() -> Vec<i32> {
+    vec![FAIL; ASIZE]
+}
+
+fn enter_pattern(
+    new_state: &mut usize,
+    pat: &[u8],
+    idx: usize,
+    goto_fn: &mut Vec<Vec<i32>>,
+    output_fn: &mut Vec<Set>,
+) {
+    let len = pat.len();
+    let mut j: usize = 0;
+    let mut state: usize = 0;
+
+    while j < len && goto_fn[state][pat[j] as usize] != FAIL {
+        state = goto_fn[state][pat[j] as usize] as usize;
+        j += 1;
+    }
+
+    for p in pat.iter().take(len).skip(j) {
+        *new_state += 1;
+        goto_fn[state][*p as usize] = *new_state as i32;
+        state = *new_state;
+        goto_fn.push(create_new_state());
+        output_fn.push(Set::new());
+    }
+
+    output_fn[state].insert(idx);
+}
+
+fn build_goto(
+    patterns: &[&[u8]],
+    goto_fn: &mut Vec<Vec<i32>>,
+    output_fn: &mut Vec<Set>,
+) {
+    let mut new_state: usize = 0;
+
+    goto_fn.push(create_new_state());
+    output_fn.push(Set::new());
+
+    for (i, pattern) in patterns.iter().enumerate() {
+        enter_pattern(&mut new_state, pattern, i, goto_fn, output_fn);
+    }
+
+    for i in 0..ASIZE {
+        if goto_fn[0][i] == FAIL {
+            goto_fn[0][i] = 0;
+        }
+    }
+}
+
+fn build_failure(goto_fn: &[Vec<i32>], output_fn: &mut [Set]) -> Vec<usize> {
+    let mut queue = Queue::new();
+    let mut failure_fn: Vec<usize> = vec![0; goto_fn.len()];
+
+    for i in ALPHA_OFFSETS {
+        let state = goto_fn[0][*i];
+        if state == 0 {
+            continue;
+        }
+
+        queue.enqueue(state as usize);
+        failure_fn[state as usize] = 0;
+    }
+
+    while !queue.is_empty() {
+        let r = queue.dequeue();
+
+        for a in ALPHA_OFFSETS {
+            let s = goto_fn[r][*a];
+            if s == FAIL {
+                continue;
+            }
+            let ss = s as usize;
+
+            queue.enqueue(ss);
+            let mut state = failure_fn[r];
+            while goto_fn[state][*a] == FAIL {
+                state = failure_fn[state];
+            }
+            failure_fn[ss] = goto_fn[state][*a] as usize;
+            let failure_set = output_fn[failure_fn[ss]].clone();
+            output_fn[ss].union(&failure_set);
+        }
+    }
+
+    failure_fn
+}
+
+/*
+    Turn the goto/failure functions into a complete transition matrix: for
+    every state `s` and every symbol `c`, follow the same resolution the
+    search loop would (take `goto_fn[s][c]` directly if it isn't FAIL,
+    otherwise walk failure links until a state has a real transition on
+    `c`), and store the result directly. State 0's unused transitions
+    already point back to itself (see `build_goto`), so the failure walk
+    for state 0 always terminates immediately.
+*/
+fn build_delta(goto_fn: &[Vec<i32>], failure_fn: &[usize]) -> Vec<Vec<i32>> {
+    let num_states = goto_fn.len();
+    let mut delta: Vec<Vec<i32>> = vec![vec![0; ASIZE]; num_states];
+
+    for s in 0..num_states {
+        for c in 0..ASIZE {
+            if goto_fn[s][c] != FAIL {
+                delta[s][c] = goto_fn[s][c];
+                continue;
+            }
+
+            let mut t = failure_fn[s];
+            while goto_fn[t][c] == FAIL {
+                t = failure_fn[t];
+            }
+            delta[s][c] = goto_fn[t][c];
+        }
+    }
+
+    delta
+}
+
+/*
+    Initialize the DFA structure for Aho-Corasick-Full and pack it into a
+    vector that can be passed to subsequent calls to `aho_corasick_full`.
+*/
+fn init_aho_corasick_full(
+    patterns: &[&[u8]],
+) -> Vec<MultiPatternData<Set>> {
+    let mut pattern_data: Vec<MultiPatternData<Set>> = Vec::with_capacity(3);
+
+    let mut goto_fn: Vec<Vec<i32>> = Vec::new();
+    let mut output_fn: Vec<Set> = Vec::new();
+    build_goto(patterns, &mut goto_fn, &mut output_fn);
+    let failure_fn = build_failure(&goto_fn, &mut output_fn);
+    let delta = build_delta(&goto_fn, &failure_fn);
+
+    pattern_data.push(MultiPatternData::PatternCount(patterns.len()));
+    pattern_data.push(MultiPatternData::PatternIntVecVec(delta));
+    pattern_data.push(MultiPatternData::PatternTypeVec(output_fn));
+
+    pattern_data
+}
+
+/*
+    Perform the Aho-Corasick algorithm against the given sequence using the
+    precomputed `delta` transition matrix: no failure-function walk is
+    needed at search time, only a single array lookup per byte.
+*/
+fn aho_corasick_full(
+    pat_data: &[MultiPatternData<Set>],
+    sequence: &[u8],
+) -> Vec<u32> {
+    let pattern_count = match &pat_data[0] {
+        MultiPatternData::PatternCount(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+    let delta = match &pat_data[1] {
+        MultiPatternData::PatternIntVecVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    };
+    let output_fn = match &pat_data[2] {
+        MultiPatternData::PatternTypeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 2"),
+    };
+
+    let mut matches: Vec<u32> = vec![0; *pattern_count];
+    let mut state: usize = 0;
+
+    for s in sequence.iter() {
+        state = delta[state][*s as usize] as usize;
+        for j in output_fn[state].iter() {
+            matches[*j] += 1;
+        }
+    }
+
+    matches
+}
+
+/*
+    All that is done here is call the run_multi() function with the argv
+    values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_multi(
+        &init_aho_corasick_full,
+        &aho_corasick_full,
+        "aho_corasick_full",
+        argv,
+    ));
+}
@@ -0,0 +1,392 @@
+/*
+    Span-reporting variant of the Aho-Corasick multi-pattern matcher.
+
+    `aho_corasick` only ever returns an occurrence count per pattern. This
+    variant instead reports every match as a `(pattern, start, end)` span,
+    under one of the three `common::run::MatchKind` semantics: `Standard`
+    (every occurrence, including overlapping ones -- the same matches
+    `aho_corasick` counts), `LeftmostFirst`, and `LeftmostLongest`.
+
+    `Set`/`Queue`/`create_new_state`/`build_failure` are identical to
+    `aho_corasick`'s (see that crate for the construction they share);
+    `enter_pattern`/`build_goto` additionally track each state's trie
+    depth, and the search loop differs to support the leftmost kinds.
+*/
+
+use common::alphabet::ByteClasses;
+use common::run::{run_multi_spans, MatchKind, MatchSpan, MultiPatternData};
+use std::env;
+use std::process::exit;
+
+const FAIL: i32 = -1;
+
+#[derive(Clone, Debug)]
+struct Set {
+    elements: Vec<usize>,
+}
+
+impl Set {
+    fn new() -> Set {
+        Set {
+            elements: Vec::with_capacity(8),
+        }
+    }
+
+    fn insert(&mut self, element: usize) {
+        self.elements.push(element);
+    }
+
+    fn contains(&self, element: usize) -> bool {
+        self.elements.contains(&element)
+    }
+
+    fn iter(&self) -> core::slice::Iter<'_, usize> {
+        self.elements.iter()
+    }
+
+    fn union(&mut self, other: &Set) {
+        for &element in other.elements.iter() {
+            if !self.contains(element) {
+                self.insert(element);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Queue {
+    elements: Vec<usize>,
+    head: usize,
+}
+
+impl Queue {
+    fn new() -> Queue {
+        Queue {
+            elements: Vec::with_capacity(32),
+            head: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head == self.elements.len()
+    }
+
+    fn enqueue(&mut self, element: usize) {
+        self.elements.push(element);
+    }
+
+    fn dequeue(&mut self) -> usize {
+        if self.is_empty() {
+            panic!("Queue::dequeue: underflow");
+        }
+        let value = self.elements[self.head];
+        self.head += 1;
+        value
+    }
+}
+
+fn create_new_state(num_classes: usize) -> Vec<i32> {
+    vec![FAIL; num_classes]
+}
+
+fn enter_pattern(
+    new_state: &mut usize,
+    pat: &[u8],
+    idx: usize,
+    goto_fn: &mut Vec<Vec<i32>>,
+    output_fn: &mut Vec<Set>,
+    depth: &mut Vec<usize>,
+    classes: &ByteClasses,
+) {
+    let len = pat.len();
+    let mut j: usize = 0;
+    let mut state: usize = 0;
+
+    while j < len && goto_fn[state][classes.class(pat[j])] != FAIL {
+        state = goto_fn[state][classes.class(pat[j])] as usize;
+        j += 1;
+    }
+
+    for (position, p) in pat.iter().enumerate().take(len).skip(j) {
+        *new_state += 1;
+        goto_fn[state][classes.class(*p)] = *new_state as i32;
+        state = *new_state;
+        goto_fn.push(create_new_state(classes.num_classes()));
+        output_fn.push(Set::new());
+        depth.push(position + 1);
+    }
+
+    output_fn[state].insert(idx);
+}
+
+fn build_goto(
+    patterns: &[&[u8]],
+    goto_fn: &mut Vec<Vec<i32>>,
+    output_fn: &mut Vec<Set>,
+    depth: &mut Vec<usize>,
+    classes: &ByteClasses,
+) {
+    let mut new_state: usize = 0;
+
+    goto_fn.push(create_new_state(classes.num_classes()));
+    output_fn.push(Set::new());
+    depth.push(0);
+
+    for (i, pattern) in patterns.iter().enumerate() {
+        enter_pattern(&mut new_state, pattern, i, goto_fn, output_fn, depth, classes);
+    }
+
+    for cl in 0..classes.num_classes() {
+        if goto_fn[0][cl] == FAIL {
+            goto_fn[0][cl] = 0;
+        }
+    }
+}
+
+fn build_failure(
+    goto_fn: &[Vec<i32>],
+    output_fn: &mut [Set],
+    classes: &ByteClasses,
+) -> Vec<usize> {
+    let mut queue = Queue::new();
+    let mut failure_fn: Vec<usize> = vec![0; goto_fn.len()];
+
+    for cl in 0..classes.num_classes() {
+        let state = goto_fn[0][cl];
+        if state == 0 {
+            continue;
+        }
+
+        queue.enqueue(state as usize);
+        failure_fn[state as usize] = 0;
+    }
+
+    while !queue.is_empty() {
+        let r = queue.dequeue();
+
+        for a in 0..classes.num_classes() {
+            let s = goto_fn[r][a];
+            if s == FAIL {
+                continue;
+            }
+            let ss = s as usize;
+
+            queue.enqueue(ss);
+            let mut state = failure_fn[r];
+            while goto_fn[state][a] == FAIL {
+                state = failure_fn[state];
+            }
+            failure_fn[ss] = goto_fn[state][a] as usize;
+            let failure_set = output_fn[failure_fn[ss]].clone();
+            output_fn[ss].union(&failure_set);
+        }
+    }
+
+    failure_fn
+}
+
+/*
+    Initialize the DFA structure for Aho-Corasick-Spans. Identical to
+    `aho_corasick::init_aho_corasick` minus its start-byte prefilter and
+    case-fold table slots (patterns and sequence bytes are matched as
+    given), plus each pattern's length (needed
+    to turn an end position into a span) and each state's trie depth --
+    the length of the longest consumed suffix the state still recognizes,
+    ignoring failure links -- which the search loop uses to tell whether
+    a pending leftmost candidate can still be extended.
+*/
+fn init_aho_corasick_spans(patterns: &[&[u8]]) -> Vec<MultiPatternData<Set>> {
+    let mut pattern_data: Vec<MultiPatternData<Set>> = Vec::with_capacity(7);
+
+    let classes = ByteClasses::from_patterns(patterns);
+
+    let mut goto_fn: Vec<Vec<i32>> = Vec::new();
+    let mut output_fn: Vec<Set> = Vec::new();
+    let mut depth: Vec<usize> = Vec::new();
+    build_goto(patterns, &mut goto_fn, &mut output_fn, &mut depth, &classes);
+    let failure_fn = build_failure(&goto_fn, &mut output_fn, &classes);
+    let pattern_lengths: Vec<usize> = patterns.iter().map(|p| p.len()).collect();
+
+    pattern_data.push(MultiPatternData::PatternCount(patterns.len()));
+    pattern_data.push(MultiPatternData::PatternIntVecVec(goto_fn));
+    pattern_data.push(MultiPatternData::PatternUsizeVec(failure_fn));
+    pattern_data.push(MultiPatternData::PatternTypeVec(output_fn));
+    pattern_data.push(MultiPatternData::PatternByteClasses(
+        classes.table(),
+        classes.num_classes(),
+    ));
+    pattern_data.push(MultiPatternData::PatternUsizeVec(pattern_lengths));
+    pattern_data.push(MultiPatternData::PatternUsizeVec(depth));
+
+    pattern_data
+}
+
+/*
+    Of two candidate spans ending at the same position (or, for `best`,
+    the single best one seen so far for the in-progress match window),
+    return the one `kind` prefers: `LeftmostLongest` prefers the smaller
+    start (leftmost), then the longer span; `LeftmostFirst` prefers the
+    smaller start, then the lower pattern index (earliest inserted).
+    `Standard` never reaches this -- every span is kept.
+*/
+fn pick_better(a: MatchSpan, b: MatchSpan, kind: MatchKind) -> MatchSpan {
+    if a.start != b.start {
+        return if a.start < b.start { a } else { b };
+    }
+
+    match kind {
+        MatchKind::LeftmostLongest => {
+            if (a.end - a.start) >= (b.end - b.start) {
+                a
+            } else {
+                b
+            }
+        }
+        MatchKind::LeftmostFirst | MatchKind::Standard => {
+            if a.pattern <= b.pattern {
+                a
+            } else {
+                b
+            }
+        }
+    }
+}
+
+/*
+    Perform the Aho-Corasick algorithm against the given sequence,
+    reporting match spans instead of a count, under the given `kind`.
+
+    For the leftmost kinds, a candidate match is tracked across positions
+    rather than emitted the moment it's found, since a longer pattern
+    sharing the same state may still complete later. `depth[state]` is
+    the length of the longest consumed suffix the current state still
+    recognizes (the trie depth, ignoring failure links); as long as it's
+    at least `pos - pending.start`, the automaton could still be on a
+    path back to a longer match starting at `pending.start`, so the
+    candidate is held. Once it drops below that, nothing reachable from
+    here can extend `pending` any further, so it's committed, the
+    earliest eligible start is advanced past its end (suppressing
+    overlaps), and scanning restarts from state 0 at its end, so a match
+    that ended while it was pending without overlapping it is still found.
+    A match still pending when the sequence runs out is committed the
+    same way.
+*/
+fn aho_corasick_spans(
+    pat_data: &[MultiPatternData<Set>],
+    sequence: &[u8],
+    kind: MatchKind,
+) -> Vec<MatchSpan> {
+    let goto_fn = match &pat_data[1] {
+        MultiPatternData::PatternIntVecVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    };
+    let failure_fn = match &pat_data[2] {
+        MultiPatternData::PatternUsizeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 2"),
+    };
+    let output_fn = match &pat_data[3] {
+        MultiPatternData::PatternTypeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 3"),
+    };
+    let classes = match &pat_data[4] {
+        MultiPatternData::PatternByteClasses(table, _) => table,
+        _ => panic!("Incorrect value at pat_data slot 4"),
+    };
+    let pattern_lengths = match &pat_data[5] {
+        MultiPatternData::PatternUsizeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 5"),
+    };
+    let depth = match &pat_data[6] {
+        MultiPatternData::PatternUsizeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 6"),
+    };
+
+    let mut spans: Vec<MatchSpan> = Vec::new();
+    let mut state: usize = 0;
+    let mut scan_from: usize = 0;
+    let mut pending: Option<MatchSpan> = None;
+    let n = sequence.len();
+    let mut i: usize = 0;
+
+    while i < n {
+        let cl = classes[sequence[i] as usize] as usize;
+        while goto_fn[state][cl] == FAIL {
+            state = failure_fn[state];
+        }
+        state = goto_fn[state][cl] as usize;
+        let pos = i + 1;
+        i += 1;
+
+        if kind == MatchKind::Standard {
+            for &j in output_fn[state].iter() {
+                let len = pattern_lengths[j];
+                spans.push(MatchSpan {
+                    pattern: j,
+                    start: pos - len,
+                    end: pos,
+                });
+            }
+            continue;
+        }
+
+        if let Some(best) = pending {
+            if depth[state] < pos - best.start {
+                spans.push(best);
+                scan_from = best.end;
+                pending = None;
+                state = 0;
+                i = best.end;
+                continue;
+            }
+        }
+
+        let mut here: Option<MatchSpan> = None;
+        for &j in output_fn[state].iter() {
+            let len = pattern_lengths[j];
+            if len > pos || pos - len < scan_from {
+                continue;
+            }
+            let candidate = MatchSpan {
+                pattern: j,
+                start: pos - len,
+                end: pos,
+            };
+            here = Some(match here {
+                None => candidate,
+                Some(best) => pick_better(best, candidate, kind),
+            });
+        }
+
+        if let Some(candidate) = here {
+            pending = Some(match pending {
+                None => candidate,
+                Some(best) => pick_better(best, candidate, kind),
+            });
+        }
+
+        if i == n {
+            if let Some(best) = pending.take() {
+                spans.push(best);
+                scan_from = best.end;
+                state = 0;
+                i = best.end;
+            }
+        }
+    }
+
+    spans
+}
+
+/*
+    All that is done here is call the run_multi_spans() function with the
+    argv values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_multi_spans(
+        &init_aho_corasick_spans,
+        &aho_corasick_spans,
+        "aho_corasick_spans",
+        argv,
+    ));
+}
@@ -0,0 +1,277 @@
+/*
+    Streaming variant of the Aho-Corasick multi-pattern matcher.
+
+    `aho_corasick` requires the entire sequence to already be in memory as
+    a `&[u8]`. This variant instead reads the sequence from a file through
+    a `BufReader` in fixed-size chunks (see `common::run::run_multi_stream`),
+    carrying the automaton's `state` from one chunk to the next -- the
+    state captures everything the automaton needs to know about what's
+    been consumed so far, so no overlap buffer between chunks is needed
+    for counting matches.
+
+    `Set`/`Queue`/`create_new_state`/`enter_pattern`/`build_goto`/
+    `build_failure` are identical to `aho_corasick`'s; see that crate for
+    the trie/failure-function construction they share. Only the search
+    step differs, since it now processes one chunk at a time instead of
+    the whole sequence in one call.
+*/
+
+use common::alphabet::ByteClasses;
+use common::run::{run_multi_stream, MultiPatternData};
+use std::env;
+use std::process::exit;
+
+const FAIL: i32 = -1;
+
+#[derive(Clone, Debug)]
+struct Set {
+    elements: Vec<usize>,
+}
+
+impl Set {
+    fn new() -> Set {
+        Set {
+            elements: Vec::with_capacity(8),
+        }
+    }
+
+    fn insert(&mut self, element: usize) {
+        self.elements.push(element);
+    }
+
+    fn contains(&self, element: usize) -> bool {
+        self.elements.contains(&element)
+    }
+
+    fn iter(&self) -> core::slice::Iter<'_, usize> {
+        self.elements.iter()
+    }
+
+    fn union(&mut self, other: &Set) {
+        for &element in other.elements.iter() {
+            if !self.contains(element) {
+                self.insert(element);
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Queue {
+    elements: Vec<usize>,
+    head: usize,
+}
+
+impl Queue {
+    fn new() -> Queue {
+        Queue {
+            elements: Vec::with_capacity(32),
+            head: 0,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.head == self.elements.len()
+    }
+
+    fn enqueue(&mut self, element: usize) {
+        self.elements.push(element);
+    }
+
+    fn dequeue(&mut self) -> usize {
+        if self.is_empty() {
+            panic!("Queue::dequeue: underflow");
+        }
+        let value = self.elements[self.head];
+        self.head += 1;
+        value
+    }
+}
+
+fn create_new_state(num_classes: usize) -> Vec<i32> {
+    vec![FAIL; num_classes]
+}
+
+fn enter_pattern(
+    new_state: &mut usize,
+    pat: &[u8],
+    idx: usize,
+    goto_fn: &mut Vec<Vec<i32>>,
+    output_fn: &mut Vec<Set>,
+    classes: &ByteClasses,
+) {
+    let len = pat.len();
+    let mut j: usize = 0;
+    let mut state: usize = 0;
+
+    while j < len && goto_fn[state][classes.class(pat[j])] != FAIL {
+        state = goto_fn[state][classes.class(pat[j])] as usize;
+        j += 1;
+    }
+
+    for p in pat.iter().take(len).skip(j) {
+        *new_state += 1;
+        goto_fn[state][classes.class(*p)] = *new_state as i32;
+        state = *new_state;
+        goto_fn.push(create_new_state(classes.num_classes()));
+        output_fn.push(Set::new());
+    }
+
+    output_fn[state].insert(idx);
+}
+
+fn build_goto(
+    patterns: &[&[u8]],
+    goto_fn: &mut Vec<Vec<i32>>,
+    output_fn: &mut Vec<Set>,
+    classes: &ByteClasses,
+) {
+    let mut new_state: usize = 0;
+
+    goto_fn.push(create_new_state(classes.num_classes()));
+    output_fn.push(Set::new());
+
+    for (i, pattern) in patterns.iter().enumerate() {
+        enter_pattern(&mut new_state, pattern, i, goto_fn, output_fn, classes);
+    }
+
+    for cl in 0..classes.num_classes() {
+        if goto_fn[0][cl] == FAIL {
+            goto_fn[0][cl] = 0;
+        }
+    }
+}
+
+fn build_failure(
+    goto_fn: &[Vec<i32>],
+    output_fn: &mut [Set],
+    classes: &ByteClasses,
+) -> Vec<usize> {
+    let mut queue = Queue::new();
+    let mut failure_fn: Vec<usize> = vec![0; goto_fn.len()];
+
+    for cl in 0..classes.num_classes() {
+        let state = goto_fn[0][cl];
+        if state == 0 {
+            continue;
+        }
+
+        queue.enqueue(state as usize);
+        failure_fn[state as usize] = 0;
+    }
+
+    while !queue.is_empty() {
+        let r = queue.dequeue();
+
+        for a in 0..classes.num_classes() {
+            let s = goto_fn[r][a];
+            if s == FAIL {
+                continue;
+            }
+            let ss = s as usize;
+
+            queue.enqueue(ss);
+            let mut state = failure_fn[r];
+            while goto_fn[state][a] == FAIL {
+                state = failure_fn[state];
+            }
+            failure_fn[ss] = goto_fn[state][a] as usize;
+            let failure_set = output_fn[failure_fn[ss]].clone();
+            output_fn[ss].union(&failure_set);
+        }
+    }
+
+    failure_fn
+}
+
+/*
+    Initialize the DFA structure for Aho-Corasick-Stream. Identical to
+    `aho_corasick::init_aho_corasick`, minus the prefilter slot, which has
+    no bearing on streaming, and minus the case-fold table slot: patterns
+    and sequence bytes are matched as given.
+*/
+fn init_aho_corasick_stream(patterns: &[&[u8]]) -> Vec<MultiPatternData<Set>> {
+    let mut pattern_data: Vec<MultiPatternData<Set>> = Vec::with_capacity(5);
+
+    let classes = ByteClasses::from_patterns(patterns);
+
+    let mut goto_fn: Vec<Vec<i32>> = Vec::new();
+    let mut output_fn: Vec<Set> = Vec::new();
+    build_goto(patterns, &mut goto_fn, &mut output_fn, &classes);
+    let failure_fn = build_failure(&goto_fn, &mut output_fn, &classes);
+
+    pattern_data.push(MultiPatternData::PatternCount(patterns.len()));
+    pattern_data.push(MultiPatternData::PatternIntVecVec(goto_fn));
+    pattern_data.push(MultiPatternData::PatternUsizeVec(failure_fn));
+    pattern_data.push(MultiPatternData::PatternTypeVec(output_fn));
+    pattern_data.push(MultiPatternData::PatternByteClasses(
+        classes.table(),
+        classes.num_classes(),
+    ));
+
+    pattern_data
+}
+
+/*
+    Perform the Aho-Corasick algorithm against one chunk of the sequence,
+    resuming from `state_in` (the automaton state left over from the
+    previous chunk, or 0 for the first) and returning the state to resume
+    from on the next chunk along with the match counts found in this one.
+*/
+fn aho_corasick_stream(
+    pat_data: &[MultiPatternData<Set>],
+    chunk: &[u8],
+    state_in: usize,
+) -> (usize, Vec<u32>) {
+    let pattern_count = match &pat_data[0] {
+        MultiPatternData::PatternCount(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+    let goto_fn = match &pat_data[1] {
+        MultiPatternData::PatternIntVecVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    };
+    let failure_fn = match &pat_data[2] {
+        MultiPatternData::PatternUsizeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 2"),
+    };
+    let output_fn = match &pat_data[3] {
+        MultiPatternData::PatternTypeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 3"),
+    };
+    let classes = match &pat_data[4] {
+        MultiPatternData::PatternByteClasses(table, _) => table,
+        _ => panic!("Incorrect value at pat_data slot 4"),
+    };
+
+    let mut matches: Vec<u32> = vec![0; *pattern_count];
+    let mut state = state_in;
+
+    for s in chunk.iter() {
+        let cl = classes[*s as usize] as usize;
+        while goto_fn[state][cl] == FAIL {
+            state = failure_fn[state];
+        }
+
+        state = goto_fn[state][cl] as usize;
+        for j in output_fn[state].iter() {
+            matches[*j] += 1;
+        }
+    }
+
+    (state, matches)
+}
+
+/*
+    All that is done here is call the run_multi_stream() function with
+    the argv values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_multi_stream(
+        &init_aho_corasick_stream,
+        &aho_corasick_stream,
+        "aho_corasick_stream",
+        argv,
+    ));
+}
@@ -5,28 +5,26 @@
     This is coded directly from the algorithm pseudo-code in the Aho-Corasick
     paper. (This Rust implementation is based on the C implementation
     previously done.)
+
+    This is the tool to reach for when the thesis comparisons call for
+    scoring the whole pattern set against a sequence in one linear pass,
+    rather than looping `run()` once per pattern the way `boyer_moore` and
+    `dfa_gap` do: `init_aho_corasick` builds the goto trie, failure links,
+    and output sets once across all patterns, and `aho_corasick` walks the
+    resulting automaton over the sequence a single time, incrementing every
+    pattern whose output set is reached at each state.
 */
 
+use common::alphabet::{case_fold_table, ByteClasses};
+use common::freq::BYTE_FREQUENCY;
 use common::run::{run_multi, MultiPatternData};
+use memchr::{memchr, memchr2, memchr3};
 use std::env;
 use std::process::exit;
 
-// Rather than implement a translation table for the four characters in the DNA
-// alphabet, for now just let the alphabet be the full ASCII range and only use
-// those four.
-const ASIZE: usize = 128;
-
 // The "fail" value is used to determine certain states in the goto function.
 const FAIL: i32 = -1;
 
-/*
-    For the creation of the failure function, we *would* loop over all of the
-    values [0, ASIZE] looking for those that are non-fail. That would be very
-    inefficient, given that our alphabet is actually just four characters. Use
-    this array to shorten those loops.
-*/
-const ALPHA_OFFSETS: &[usize] = &[65, 67, 71, 84];
-
 /*
     This basic "set" implementation was provided by Andrew Gallant when helping
     me determine the reason for this version being so much slower than the C
@@ -103,11 +101,13 @@ impl Queue {
 }
 
 /*
-    Simple function to create a new state for the goto_fn.
+    Simple function to create a new state for the goto_fn. Rows are one
+    column per byte equivalence class (see `common::alphabet`) rather than
+    one per possible byte, so for a DNA pattern set this is about five
+    wide instead of 128.
 */
-fn create_new_state() -> Vec<i32> {
-    let new_state = vec![FAIL; ASIZE];
-    new_state
+fn create_new_state(num_classes: usize) -> Vec<i32> {
+    vec![FAIL; num_classes]
 }
 
 /*
@@ -125,6 +125,7 @@ fn enter_pattern(
     idx: usize,
     goto_fn: &mut Vec<Vec<i32>>,
     output_fn: &mut Vec<Set>,
+    classes: &ByteClasses,
 ) {
     let len = pat.len();
     let mut j: usize = 0;
@@ -132,8 +133,8 @@ fn enter_pattern(
 
     // Find the first leaf corresponding to a character in `pat`. From there is
     // where a new state (if needed) will be added.
-    while goto_fn[state][pat[j] as usize] != FAIL {
-        state = goto_fn[state][pat[j] as usize] as usize;
+    while j < len && goto_fn[state][classes.class(pat[j])] != FAIL {
+        state = goto_fn[state][classes.class(pat[j])] as usize;
         j += 1;
     }
 
@@ -142,13 +143,13 @@ fn enter_pattern(
     // already in the automaton.
     for p in pat.iter().take(len).skip(j) {
         *new_state += 1;
-        goto_fn[state][*p as usize] = *new_state as i32;
+        goto_fn[state][classes.class(*p)] = *new_state as i32;
         state = *new_state;
         // Unlike the C code, the availability of Vec as a native type allows
         // the automaton to be dynamically grown as needed. So we have to
         // create the new state and append it to goto_fn. Also have to create
         // a new set object and add it to output_fn.
-        goto_fn.push(create_new_state());
+        goto_fn.push(create_new_state(classes.num_classes()));
         output_fn.push(Set::new());
     }
 
@@ -163,6 +164,7 @@ fn build_goto(
     patterns: &[&[u8]],
     goto_fn: &mut Vec<Vec<i32>>,
     output_fn: &mut Vec<Set>,
+    classes: &ByteClasses,
 ) {
     // This value tracks the current high state number and is used in
     // successive calls to enter_pattern() to know what index new states are
@@ -170,26 +172,35 @@ fn build_goto(
     let mut new_state: usize = 0;
 
     // Initialize state 0 for goto_fn and output_fn.
-    goto_fn.push(create_new_state());
+    goto_fn.push(create_new_state(classes.num_classes()));
     output_fn.push(Set::new());
 
     // Add each pattern in turn:
     for (i, pattern) in patterns.iter().enumerate() {
-        enter_pattern(&mut new_state, pattern, i, goto_fn, output_fn);
+        enter_pattern(&mut new_state, pattern, i, goto_fn, output_fn, classes);
     }
 
     // Set any unused transitions in state 0 to point back to state 0:
-    for i in 0..ASIZE {
-        if goto_fn[0][i] == FAIL {
-            goto_fn[0][i] = 0;
+    for cl in 0..classes.num_classes() {
+        if goto_fn[0][cl] == FAIL {
+            goto_fn[0][cl] = 0;
         }
     }
 }
 
 /*
     Build the failure function and complete the output function.
+
+    This used to loop only over `ALPHA_OFFSETS` to avoid walking all 128
+    columns of a row; now that rows are compressed to one column per
+    equivalence class (see `common::alphabet`), looping over every class
+    directly is just as cheap and needs no such shortcut.
 */
-fn build_failure(goto_fn: &[Vec<i32>], output_fn: &mut [Set]) -> Vec<usize> {
+fn build_failure(
+    goto_fn: &[Vec<i32>],
+    output_fn: &mut [Set],
+    classes: &ByteClasses,
+) -> Vec<usize> {
     // Need a queue of state numbers:
     let mut queue = Queue::new();
 
@@ -199,8 +210,8 @@ fn build_failure(goto_fn: &[Vec<i32>], output_fn: &mut [Set]) -> Vec<usize> {
 
     // The queue starts out empty. Set it to be all states reachable from state
     // 0 and set failure(state) for those states to be 0.
-    for i in ALPHA_OFFSETS {
-        let state = goto_fn[0][*i];
+    for cl in 0..classes.num_classes() {
+        let state = goto_fn[0][cl];
         if state == 0 {
             continue;
         }
@@ -215,8 +226,8 @@ fn build_failure(goto_fn: &[Vec<i32>], output_fn: &mut [Set]) -> Vec<usize> {
     while !queue.is_empty() {
         let r = queue.dequeue();
 
-        for a in ALPHA_OFFSETS {
-            let s = goto_fn[r][*a];
+        for a in 0..classes.num_classes() {
+            let s = goto_fn[r][a];
             if s == FAIL {
                 continue;
             }
@@ -224,10 +235,10 @@ fn build_failure(goto_fn: &[Vec<i32>], output_fn: &mut [Set]) -> Vec<usize> {
 
             queue.enqueue(ss);
             let mut state = failure_fn[r];
-            while goto_fn[state][*a] == FAIL {
+            while goto_fn[state][a] == FAIL {
                 state = failure_fn[state];
             }
-            failure_fn[ss] = goto_fn[state][*a] as usize;
+            failure_fn[ss] = goto_fn[state][a] as usize;
             let failure_set = output_fn[failure_fn[ss]].clone();
             output_fn[ss].union(&failure_set);
         }
@@ -236,23 +247,83 @@ fn build_failure(goto_fn: &[Vec<i32>], output_fn: &mut [Set]) -> Vec<usize> {
     failure_fn
 }
 
+/*
+    Collect the distinct depth-1 bytes across all patterns -- the non-zero
+    targets of `goto_fn[0]`, i.e. every byte a match could possibly start
+    with -- ordered rarest-first by `BYTE_FREQUENCY`. Gated behind the
+    `AC_PREFILTER` environment variable (see `AUTOMATON_CACHE` in
+    `common::run` for the same opt-in-toggle convention); when unset, this
+    returns an empty vector and `aho_corasick` skips the prefilter
+    entirely, matching its previous plain-linear-scan behavior.
+
+    Also skipped whenever `CASE_INSENSITIVE` is set: the prefilter's
+    `memchr` calls match a start byte literally against the raw sequence,
+    while case-insensitive mode folds every sequence byte before it reaches
+    the automaton, so the two would need reconciling (matching either case
+    of each start byte) to stay correct. Simpler to just fall back to the
+    plain per-byte scan, which already folds correctly, when both are on.
+*/
+fn start_bytes(patterns: &[&[u8]]) -> Vec<u8> {
+    if env::var("AC_PREFILTER").is_err() || env::var("CASE_INSENSITIVE").is_ok() {
+        return Vec::new();
+    }
+
+    let mut bytes: Vec<u8> = Vec::new();
+    for pattern in patterns {
+        let b = pattern[0];
+        if !bytes.contains(&b) {
+            bytes.push(b);
+        }
+    }
+    bytes.sort_by_key(|&b| BYTE_FREQUENCY[b as usize]);
+
+    bytes
+}
+
 /*
     Initialize the DFA structure for Aho-Corasick and pack it into a vector
     that can be passed to subsequent calls to `aho_corasick` itself.
+
+    Case-insensitive matching is opt-in via the `CASE_INSENSITIVE`
+    environment variable (see `AUTOMATON_CACHE` in `common::run` for the
+    same opt-in-toggle convention). When it's set, the patterns are folded
+    to lowercase before the trie/byte-classes are built from them, and the
+    fold table is packed alongside so `aho_corasick` can fold each sequence
+    byte the same way before indexing the automaton; when it's not set, the
+    table is the identity mapping and folding is a no-op.
 */
 fn init_aho_corasick(patterns: &[&[u8]]) -> Vec<MultiPatternData<Set>> {
-    let mut pattern_data: Vec<MultiPatternData<Set>> = Vec::with_capacity(4);
+    let mut pattern_data: Vec<MultiPatternData<Set>> = Vec::with_capacity(7);
+
+    let fold = case_fold_table(env::var("CASE_INSENSITIVE").is_ok());
+    let folded_patterns: Vec<Vec<u8>> = patterns
+        .iter()
+        .map(|pat| pat.iter().map(|&b| fold[b as usize]).collect())
+        .collect();
+    let folded_refs: Vec<&[u8]> =
+        folded_patterns.iter().map(|pat| pat.as_slice()).collect();
+
+    // Collapse the goto-function rows down to one column per equivalence
+    // class across all patterns (for a DNA pattern set, one of A/C/G/T
+    // plus "other") instead of one per possible byte.
+    let classes = ByteClasses::from_patterns(&folded_refs);
 
     // Initialize the multi-patterns structure.
     let mut goto_fn: Vec<Vec<i32>> = Vec::new();
     let mut output_fn: Vec<Set> = Vec::new();
-    build_goto(patterns, &mut goto_fn, &mut output_fn);
-    let failure_fn = build_failure(&goto_fn, &mut output_fn);
+    build_goto(&folded_refs, &mut goto_fn, &mut output_fn, &classes);
+    let failure_fn = build_failure(&goto_fn, &mut output_fn, &classes);
 
     pattern_data.push(MultiPatternData::PatternCount(patterns.len()));
     pattern_data.push(MultiPatternData::PatternIntVecVec(goto_fn));
     pattern_data.push(MultiPatternData::PatternUsizeVec(failure_fn));
     pattern_data.push(MultiPatternData::PatternTypeVec(output_fn));
+    pattern_data.push(MultiPatternData::PatternByteClasses(
+        classes.table(),
+        classes.num_classes(),
+    ));
+    pattern_data.push(MultiPatternData::PatternStartBytes(start_bytes(patterns)));
+    pattern_data.push(MultiPatternData::PatternFoldTable(fold));
 
     pattern_data
 }
@@ -264,6 +335,15 @@ fn init_aho_corasick(patterns: &[&[u8]]) -> Vec<MultiPatternData<Set>> {
 
     Instead of returning a single u32, this returns a Vec<u32> with size equal
     to `pattern_count`.
+
+    When `start_bytes` is non-empty (the `AC_PREFILTER` flag was set at
+    init time), every time the automaton falls back to state 0 this skips
+    ahead to the next position whose byte could possibly start a match,
+    rather than stepping through the dead stretch between matches one
+    byte at a time. `memchr`/`memchr2`/`memchr3` cover the common case of
+    up to three distinct pattern-prefix bytes (a typical DNA pattern set
+    rarely needs more); beyond that, a plain scan over `start_bytes` is
+    used, since `memchr`'s fixed-arity variants don't generalize further.
 */
 fn aho_corasick(
     pat_data: &[MultiPatternData<Set>],
@@ -286,19 +366,48 @@ fn aho_corasick(
         MultiPatternData::PatternTypeVec(val) => val,
         _ => panic!("Incorrect value at pat_data slot 3"),
     };
+    let classes = match &pat_data[4] {
+        MultiPatternData::PatternByteClasses(table, _) => table,
+        _ => panic!("Incorrect value at pat_data slot 4"),
+    };
+    let start_bytes = match &pat_data[5] {
+        MultiPatternData::PatternStartBytes(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 5"),
+    };
+    let fold = match &pat_data[6] {
+        MultiPatternData::PatternFoldTable(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 6"),
+    };
 
     let mut matches: Vec<u32> = vec![0; *pattern_count];
     let mut state: usize = 0;
+    let mut i: usize = 0;
+    let n = sequence.len();
+
+    while i < n {
+        if state == 0 && !start_bytes.is_empty() {
+            let skip = match start_bytes.as_slice() {
+                [a] => memchr(*a, &sequence[i..]),
+                [a, b] => memchr2(*a, *b, &sequence[i..]),
+                [a, b, c] => memchr3(*a, *b, *c, &sequence[i..]),
+                rest => sequence[i..].iter().position(|b| rest.contains(b)),
+            };
+            match skip {
+                Some(delta) => i += delta,
+                None => break,
+            }
+        }
 
-    for s in sequence.iter() {
-        while goto_fn[state][*s as usize] == FAIL {
+        let cl = classes[fold[sequence[i] as usize] as usize] as usize;
+        while goto_fn[state][cl] == FAIL {
             state = failure_fn[state];
         }
 
-        state = goto_fn[state][*s as usize] as usize;
+        state = goto_fn[state][cl] as usize;
         for j in output_fn[state].iter() {
             matches[*j] += 1;
         }
+        i += 1;
     }
 
     matches
@@ -6,23 +6,25 @@
     Thierry Lecroq.
 */
 
+use common::alphabet::ByteClasses;
+use common::freq::rarest_byte_offset;
 use common::run::{run, PatternData};
+use memchr::memchr;
 use std::cmp::max;
 use std::env;
 use std::process::exit;
 
-// Define the alphabet size, part of the Boyer-Moore pre-processing. Here, we
-// are just using ASCII characters, so 128 is fine.
-const ASIZE: usize = 128;
-
 /*
-    Preprocessing step: calculate the bad-character shifts.
+    Preprocessing step: calculate the bad-character shifts. Rather than a row
+    as wide as the byte range, this is indexed by equivalence class (see
+    `common::alphabet`), so for a DNA pattern it ends up five-wide (one class
+    per base plus "other") instead of 128-wide.
 */
-fn calc_bad_char(pat: &[u8], m: usize) -> Vec<i32> {
-    let mut bad_char: Vec<i32> = vec![m as i32; ASIZE];
+fn calc_bad_char(pat: &[u8], m: usize, classes: &ByteClasses) -> Vec<i32> {
+    let mut bad_char: Vec<i32> = vec![m as i32; classes.num_classes()];
 
     for i in 0..(m - 1) {
-        bad_char[pat[i] as usize] = (m - i - 1) as i32;
+        bad_char[classes.class(pat[i])] = (m - i - 1) as i32;
     }
 
     bad_char
@@ -100,7 +102,7 @@ fn calc_good_suffix(pat: &[u8], m: usize) -> Vec<i32> {
 */
 fn init_boyer_moore(pat: &[u8]) -> Vec<PatternData> {
     let m = pat.len();
-    let mut pattern_data: Vec<PatternData> = Vec::with_capacity(3);
+    let mut pattern_data: Vec<PatternData> = Vec::with_capacity(4);
 
     // Because the C code takes advantage of the presence of a null byte at the
     // end of strings, we have to force this in and re-convert the pattern to a
@@ -109,13 +111,27 @@ fn init_boyer_moore(pat: &[u8]) -> Vec<PatternData> {
     new_vec.push(0);
     let new_pat = new_vec.as_slice();
 
+    // Collapse the bad-character table's columns down to one per
+    // equivalence class (for a DNA pattern, one of A/C/G/T plus "other")
+    // instead of one per possible byte.
+    let classes = ByteClasses::from_pattern(pat);
+
     // Get the bad-character and good-suffix shift tables:
     let good_suffix: Vec<i32> = calc_good_suffix(new_pat, m);
-    let bad_char: Vec<i32> = calc_bad_char(new_pat, m);
+    let bad_char: Vec<i32> = calc_bad_char(new_pat, m, &classes);
 
     pattern_data.push(PatternData::PatternU8Vec(new_pat.to_owned()));
     pattern_data.push(PatternData::PatternIntVec(good_suffix));
     pattern_data.push(PatternData::PatternIntVec(bad_char));
+    pattern_data
+        .push(PatternData::PatternByteClasses(classes.table(), classes.num_classes()));
+
+    // Record the pattern's rarest byte and its offset, so `boyer_moore`'s
+    // own search loop can use `memchr` to skip straight to the next
+    // alignment where that byte could possibly land, instead of running
+    // the good-suffix/bad-character comparison at every `j`.
+    let rare_offset = rarest_byte_offset(pat);
+    pattern_data.push(PatternData::PatternRareByte(pat[rare_offset], rare_offset));
 
     pattern_data
 }
@@ -143,6 +159,14 @@ fn boyer_moore(pat_data: &[PatternData], sequence: &[u8]) -> i32 {
         PatternData::PatternIntVec(arr) => arr,
         _ => panic!("Incorrect value at pat_data slot 2"),
     };
+    let classes = match &pat_data[3] {
+        PatternData::PatternByteClasses(table, _) => table,
+        _ => panic!("Incorrect value at pat_data slot 3"),
+    };
+    let (rare_byte, rare_offset) = match &pat_data[4] {
+        PatternData::PatternRareByte(byte, offset) => (*byte, *offset as i32),
+        _ => panic!("Incorrect value at pat_data slot 4"),
+    };
 
     // Sizes of pattern and sequence. Converted from usize to i32 to cut down
     // on the number of casts that have to be done. The casts don't really
@@ -153,6 +177,26 @@ fn boyer_moore(pat_data: &[PatternData], sequence: &[u8]) -> i32 {
     // Perform the searching:
     j = 0;
     while j <= n - m {
+        // An alignment at `j` can only succeed if the pattern's rarest byte
+        // is present at its offset within the alignment. Rather than run
+        // the good-suffix/bad-character comparison to discover that, use
+        // `memchr` to jump straight to the next sequence position where it
+        // is, skipping every alignment in between in one step.
+        if sequence[(j + rare_offset) as usize] != rare_byte {
+            let search_from = (j + rare_offset) as usize + 1;
+            match memchr(rare_byte, &sequence[search_from..]) {
+                Some(delta) => {
+                    let new_j = (search_from + delta) as i32 - rare_offset;
+                    if new_j > n - m {
+                        break;
+                    }
+                    j = new_j;
+                    continue;
+                }
+                None => break,
+            }
+        }
+
         i = m - 1;
         while i >= 0 && pattern[i as usize] == sequence[(i + j) as usize] {
             i -= 1;
@@ -163,7 +207,10 @@ fn boyer_moore(pat_data: &[PatternData], sequence: &[u8]) -> i32 {
         } else {
             j += max(
                 good_suffix[i as usize],
-                bad_char[sequence[(i + j) as usize] as usize] - m + 1 + i,
+                bad_char[classes[sequence[(i + j) as usize] as usize] as usize]
+                    - m
+                    + 1
+                    + i,
             );
         }
     }
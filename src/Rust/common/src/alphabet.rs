@@ -0,0 +1,105 @@
+/*
+    A small byte-equivalence-class subsystem, mirroring the alphabet
+    byte-class idea used by the `regex-automata` crate's `util::alphabet`
+    module: rather than give every DFA/shift-table row a column for each of
+    the 256 possible input bytes, partition the byte range into classes such
+    that two bytes are interchangeable whenever the automaton being built
+    never needs to tell them apart. For the DNA patterns this project works
+    with, that partition collapses to one class per base that appears in the
+    pattern(s) plus a single catch-all "other" class -- five columns instead
+    of 128 (or 256).
+*/
+
+/*
+    `byte_to_class` maps every possible input byte down to its equivalence
+    class; `num_classes` is one more than the highest class number in use, so
+    callers can size a `num_classes`-wide row per DFA/shift-table state.
+*/
+pub struct ByteClasses {
+    byte_to_class: [u8; 256],
+    num_classes: usize,
+}
+
+impl ByteClasses {
+    /*
+        Build the byte classes for a set of patterns: every distinct byte
+        appearing in any of `patterns` gets its own class, in ascending
+        order by byte value, and every byte that never appears is folded
+        into one final "other" class.
+    */
+    pub fn from_patterns(patterns: &[&[u8]]) -> ByteClasses {
+        let mut seen = [false; 256];
+        for pat in patterns {
+            for &b in pat.iter() {
+                seen[b as usize] = true;
+            }
+        }
+
+        let mut byte_to_class = [0u8; 256];
+        let mut next_class: usize = 0;
+        for (b, &is_seen) in seen.iter().enumerate() {
+            if is_seen {
+                byte_to_class[b] = next_class as u8;
+                next_class += 1;
+            }
+        }
+        // Every unseen byte shares the final, catch-all "other" class.
+        let other_class = next_class;
+        for (b, &is_seen) in seen.iter().enumerate() {
+            if !is_seen {
+                byte_to_class[b] = other_class as u8;
+            }
+        }
+
+        ByteClasses {
+            byte_to_class,
+            num_classes: other_class + 1,
+        }
+    }
+
+    /*
+        Convenience constructor for the common case of a single pattern.
+    */
+    pub fn from_pattern(pattern: &[u8]) -> ByteClasses {
+        ByteClasses::from_patterns(&[pattern])
+    }
+
+    /*
+        Map a single byte to its equivalence class.
+    */
+    #[inline]
+    pub fn class(&self, byte: u8) -> usize {
+        self.byte_to_class[byte as usize] as usize
+    }
+
+    /*
+        The number of distinct classes, i.e. the width to use for a
+        compressed DFA/shift-table row.
+    */
+    pub fn num_classes(&self) -> usize {
+        self.num_classes
+    }
+
+    /*
+        Expose the full lookup table, for callers that want to pack it
+        directly into their own `PatternData`/`ApproxPatternData` vector
+        rather than carrying a `ByteClasses` value around.
+    */
+    pub fn table(&self) -> Vec<u8> {
+        self.byte_to_class.to_vec()
+    }
+}
+
+/*
+    Build the ASCII case-fold lookup table backing the `CASE_INSENSITIVE`
+    flag shared by `kmp` and `aho_corasick`: `table[b]` is the lowercase
+    form of `b` when `enabled`, or plain `b` (the identity table) when it's
+    not. Callers index every pattern and sequence byte through this table
+    unconditionally, so the flag only has to be checked once, at init time,
+    rather than at every byte compared.
+*/
+pub fn case_fold_table(enabled: bool) -> Vec<u8> {
+    (0..=255u8)
+        .map(|b| if enabled { b.to_ascii_lowercase() } else { b })
+        .collect()
+}
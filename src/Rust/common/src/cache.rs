@@ -0,0 +1,403 @@
+/*
+    Optional on-disk caching of precomputed pattern automata, so that
+    re-running the same pattern (and, for approximate matching, the same
+    `k`) against a large corpus doesn't repay the preprocessing cost --
+    `calc_good_suffix`/`calc_bad_char` for `boyer_moore`, `create_dfa` for
+    `dfa_gap`, and so on -- on every invocation.
+
+    Caching is opt-in, in the same style as the `PARALLEL` toggle in
+    `common::run`: set the `AUTOMATON_CACHE` environment variable to a
+    directory, and `run()`/`run_approx()` will look there for a file
+    matching the current algorithm, pattern, and `k` before calling the
+    algorithm's `init`, loading it when present and writing a freshly
+    built one otherwise.
+
+    The on-disk format follows the same shape as regex-automata's table
+    (de)serialization: a small fixed header -- magic bytes, a format
+    version, an endianness marker (always little-endian, since every value
+    is written little-endian whatever the host; a file carrying any other
+    marker is rejected), the pattern
+    length, `k`, and the number of packed entries -- followed by one
+    tagged block per `PatternData`/`ApproxPatternData` variant. Each block
+    is just that variant's raw `u8`/`i32`/`u64` values, length-prefixed
+    where the value is a vector, all little-endian.
+*/
+
+use crate::run::{ApproxPatternData, PatternData};
+use std::fs;
+use std::io;
+
+const MAGIC: &[u8; 4] = b"MSCA";
+const VERSION: u8 = 1;
+const ENDIAN_LE: u8 = 1;
+const HEADER_LEN: usize = 18;
+
+/*
+    The cache file for a given algorithm/pattern/k, rooted at `dir`. The
+    file name folds in the pattern bytes themselves (not just a hash of
+    them), so two different patterns of the same length never collide.
+*/
+fn cache_path(dir: &str, name: &str, pattern: &[u8], k: u32) -> String {
+    let pattern_hex: String =
+        pattern.iter().map(|b| format!("{:02x}", b)).collect();
+
+    format!("{}/{}-k{}-{}.bin", dir, name, k, pattern_hex)
+}
+
+fn write_header(out: &mut Vec<u8>, pattern_len: usize, k: u32, num_entries: usize) {
+    out.extend_from_slice(MAGIC);
+    out.push(VERSION);
+    out.push(ENDIAN_LE);
+    out.extend_from_slice(&(pattern_len as u32).to_le_bytes());
+    out.extend_from_slice(&k.to_le_bytes());
+    out.extend_from_slice(&(num_entries as u32).to_le_bytes());
+}
+
+/*
+    Validate the header at the front of `buf` against the pattern/k the
+    caller is about to search with. Returns the number of packed entries
+    on a match, or `None` if the file is missing, malformed, or was built
+    for a different pattern/k/format version -- any of which just means
+    the caller should fall back to rebuilding from scratch.
+*/
+fn read_header(buf: &[u8], pattern_len: usize, k: u32) -> Option<usize> {
+    if buf.len() < HEADER_LEN || &buf[0..4] != MAGIC {
+        return None;
+    }
+    if buf[4] != VERSION || buf[5] != ENDIAN_LE {
+        return None;
+    }
+    let stored_pattern_len =
+        u32::from_le_bytes(buf[6..10].try_into().unwrap()) as usize;
+    let stored_k = u32::from_le_bytes(buf[10..14].try_into().unwrap());
+    if stored_pattern_len != pattern_len || stored_k != k {
+        return None;
+    }
+
+    Some(u32::from_le_bytes(buf[14..18].try_into().unwrap()) as usize)
+}
+
+// A cursor over a cache file's body, used by the per-variant readers
+// below. Every read returns `None` on truncation instead of panicking,
+// so a corrupted cache file is treated as a miss rather than crashing the
+// run.
+struct Cursor<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn read_u8(&mut self) -> Option<u8> {
+        let byte = *self.buf.get(self.pos)?;
+        self.pos += 1;
+        Some(byte)
+    }
+
+    fn read_u32(&mut self) -> Option<u32> {
+        let bytes = self.buf.get(self.pos..self.pos + 4)?;
+        self.pos += 4;
+        Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u64(&mut self) -> Option<u64> {
+        let bytes = self.buf.get(self.pos..self.pos + 8)?;
+        self.pos += 8;
+        Some(u64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+
+    fn read_u8_vec(&mut self) -> Option<Vec<u8>> {
+        let len = self.read_u32()? as usize;
+        let bytes = self.buf.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(bytes.to_vec())
+    }
+
+    fn read_i32_vec(&mut self) -> Option<Vec<i32>> {
+        let len = self.read_u32()? as usize;
+        let mut v = Vec::with_capacity(len);
+        for _ in 0..len {
+            v.push(self.read_u32()? as i32);
+        }
+        Some(v)
+    }
+
+    fn read_u64_vec(&mut self) -> Option<Vec<u64>> {
+        let len = self.read_u32()? as usize;
+        let mut v = Vec::with_capacity(len);
+        for _ in 0..len {
+            v.push(self.read_u64()?);
+        }
+        Some(v)
+    }
+
+    fn read_i32_vecvec(&mut self) -> Option<Vec<Vec<i32>>> {
+        let len = self.read_u32()? as usize;
+        let mut v = Vec::with_capacity(len);
+        for _ in 0..len {
+            v.push(self.read_i32_vec()?);
+        }
+        Some(v)
+    }
+
+    fn read_sparse_vecvec(&mut self) -> Option<Vec<Vec<(u8, i32)>>> {
+        let len = self.read_u32()? as usize;
+        let mut v = Vec::with_capacity(len);
+        for _ in 0..len {
+            let row_len = self.read_u32()? as usize;
+            let mut row = Vec::with_capacity(row_len);
+            for _ in 0..row_len {
+                let class = self.read_u8()?;
+                let next = self.read_u32()? as i32;
+                row.push((class, next));
+            }
+            v.push(row);
+        }
+        Some(v)
+    }
+}
+
+fn write_u8_vec(out: &mut Vec<u8>, v: &[u8]) {
+    out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+    out.extend_from_slice(v);
+}
+
+fn write_i32_vec(out: &mut Vec<u8>, v: &[i32]) {
+    out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+    for x in v {
+        out.extend_from_slice(&(*x as u32).to_le_bytes());
+    }
+}
+
+fn write_u64_vec(out: &mut Vec<u8>, v: &[u64]) {
+    out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+    for x in v {
+        out.extend_from_slice(&x.to_le_bytes());
+    }
+}
+
+fn write_i32_vecvec(out: &mut Vec<u8>, v: &[Vec<i32>]) {
+    out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+    for row in v {
+        write_i32_vec(out, row);
+    }
+}
+
+fn write_sparse_vecvec(out: &mut Vec<u8>, v: &[Vec<(u8, i32)>]) {
+    out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+    for row in v {
+        out.extend_from_slice(&(row.len() as u32).to_le_bytes());
+        for &(class, next) in row {
+            out.push(class);
+            out.extend_from_slice(&(next as u32).to_le_bytes());
+        }
+    }
+}
+
+fn write_entry(out: &mut Vec<u8>, entry: &PatternData) {
+    match entry {
+        PatternData::PatternU8Vec(v) => {
+            out.push(0);
+            write_u8_vec(out, v);
+        }
+        PatternData::PatternIntVec(v) => {
+            out.push(1);
+            write_i32_vec(out, v);
+        }
+        PatternData::PatternWord(w) => {
+            out.push(2);
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        PatternData::PatternWordVec(v) => {
+            out.push(3);
+            write_u64_vec(out, v);
+        }
+        PatternData::PatternAnchor(byte, offset) => {
+            out.push(4);
+            out.push(*byte);
+            out.extend_from_slice(&(*offset as u32).to_le_bytes());
+        }
+        PatternData::PatternRareByte(byte, offset) => {
+            out.push(5);
+            out.push(*byte);
+            out.extend_from_slice(&(*offset as u32).to_le_bytes());
+        }
+        PatternData::PatternByteClasses(table, num_classes) => {
+            out.push(6);
+            write_u8_vec(out, table);
+            out.extend_from_slice(&(*num_classes as u32).to_le_bytes());
+        }
+        PatternData::PatternFoldTable(table) => {
+            out.push(7);
+            write_u8_vec(out, table);
+        }
+    }
+}
+
+fn read_entry(cur: &mut Cursor) -> Option<PatternData> {
+    Some(match cur.read_u8()? {
+        0 => PatternData::PatternU8Vec(cur.read_u8_vec()?),
+        1 => PatternData::PatternIntVec(cur.read_i32_vec()?),
+        2 => PatternData::PatternWord(cur.read_u64()?),
+        3 => PatternData::PatternWordVec(cur.read_u64_vec()?),
+        4 => PatternData::PatternAnchor(cur.read_u8()?, cur.read_u32()? as usize),
+        5 => {
+            PatternData::PatternRareByte(cur.read_u8()?, cur.read_u32()? as usize)
+        }
+        6 => PatternData::PatternByteClasses(
+            cur.read_u8_vec()?,
+            cur.read_u32()? as usize,
+        ),
+        7 => PatternData::PatternFoldTable(cur.read_u8_vec()?),
+        _ => return None,
+    })
+}
+
+fn write_approx_entry(out: &mut Vec<u8>, entry: &ApproxPatternData) {
+    match entry {
+        ApproxPatternData::PatternIntVecVec(v) => {
+            out.push(0);
+            write_i32_vecvec(out, v);
+        }
+        ApproxPatternData::PatternUsize(val) => {
+            out.push(1);
+            out.extend_from_slice(&(*val as u32).to_le_bytes());
+        }
+        ApproxPatternData::PatternWord(w) => {
+            out.push(2);
+            out.extend_from_slice(&w.to_le_bytes());
+        }
+        ApproxPatternData::PatternWordVec(v) => {
+            out.push(3);
+            write_u64_vec(out, v);
+        }
+        ApproxPatternData::PatternWordVecVec(v) => {
+            out.push(4);
+            out.extend_from_slice(&(v.len() as u32).to_le_bytes());
+            for row in v {
+                write_u64_vec(out, row);
+            }
+        }
+        ApproxPatternData::PatternByteClasses(table, num_classes) => {
+            out.push(5);
+            write_u8_vec(out, table);
+            out.extend_from_slice(&(*num_classes as u32).to_le_bytes());
+        }
+        ApproxPatternData::PatternSparseDfa(v) => {
+            out.push(6);
+            write_sparse_vecvec(out, v);
+        }
+    }
+}
+
+fn read_approx_entry(cur: &mut Cursor) -> Option<ApproxPatternData> {
+    Some(match cur.read_u8()? {
+        0 => ApproxPatternData::PatternIntVecVec(cur.read_i32_vecvec()?),
+        1 => ApproxPatternData::PatternUsize(cur.read_u32()? as usize),
+        2 => ApproxPatternData::PatternWord(cur.read_u64()?),
+        3 => ApproxPatternData::PatternWordVec(cur.read_u64_vec()?),
+        4 => {
+            let len = cur.read_u32()? as usize;
+            let mut v = Vec::with_capacity(len);
+            for _ in 0..len {
+                v.push(cur.read_u64_vec()?);
+            }
+            ApproxPatternData::PatternWordVecVec(v)
+        }
+        5 => ApproxPatternData::PatternByteClasses(
+            cur.read_u8_vec()?,
+            cur.read_u32()? as usize,
+        ),
+        6 => ApproxPatternData::PatternSparseDfa(cur.read_sparse_vecvec()?),
+        _ => return None,
+    })
+}
+
+/*
+    Load the cached automaton for `name`/`pattern` from `dir`, if one
+    exists and matches. Used by `run()`.
+*/
+pub fn load_pattern_data(
+    dir: &str,
+    name: &str,
+    pattern: &[u8],
+) -> Option<Vec<PatternData>> {
+    let buf = fs::read(cache_path(dir, name, pattern, 0)).ok()?;
+    let num_entries = read_header(&buf, pattern.len(), 0)?;
+    let mut cur = Cursor {
+        buf: &buf,
+        pos: HEADER_LEN,
+    };
+    let mut data = Vec::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        data.push(read_entry(&mut cur)?);
+    }
+
+    Some(data)
+}
+
+/*
+    Write the just-built automaton for `name`/`pattern` to `dir`, creating
+    the directory if needed. Used by `run()`.
+*/
+pub fn save_pattern_data(
+    dir: &str,
+    name: &str,
+    pattern: &[u8],
+    data: &[PatternData],
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut out = Vec::new();
+    write_header(&mut out, pattern.len(), 0, data.len());
+    for entry in data {
+        write_entry(&mut out, entry);
+    }
+
+    fs::write(cache_path(dir, name, pattern, 0), out)
+}
+
+/*
+    Load the cached automaton for `name`/`pattern`/`k` from `dir`, if one
+    exists and matches. Used by `run_approx()`.
+*/
+pub fn load_approx_pattern_data(
+    dir: &str,
+    name: &str,
+    pattern: &[u8],
+    k: u32,
+) -> Option<Vec<ApproxPatternData>> {
+    let buf = fs::read(cache_path(dir, name, pattern, k)).ok()?;
+    let num_entries = read_header(&buf, pattern.len(), k)?;
+    let mut cur = Cursor {
+        buf: &buf,
+        pos: HEADER_LEN,
+    };
+    let mut data = Vec::with_capacity(num_entries);
+    for _ in 0..num_entries {
+        data.push(read_approx_entry(&mut cur)?);
+    }
+
+    Some(data)
+}
+
+/*
+    Write the just-built automaton for `name`/`pattern`/`k` to `dir`,
+    creating the directory if needed. Used by `run_approx()`.
+*/
+pub fn save_approx_pattern_data(
+    dir: &str,
+    name: &str,
+    pattern: &[u8],
+    k: u32,
+    data: &[ApproxPatternData],
+) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+
+    let mut out = Vec::new();
+    write_header(&mut out, pattern.len(), k, data.len());
+    for entry in data {
+        write_approx_entry(&mut out, entry);
+    }
+
+    fs::write(cache_path(dir, name, pattern, k), out)
+}
@@ -0,0 +1,49 @@
+/*
+    A small table of approximate relative byte frequencies, used to pick a
+    "rare" byte within a pattern to anchor prefilters around -- the same
+    idea behind the literal prefilters in the aho-corasick crate. Lower
+    values mean rarer bytes.
+
+    The values below are weighted toward the DNA alphabet this project's
+    matchers actually run against (uppercase A/C/G/T are the most common
+    bytes by far, lowercase acgt somewhat less so for soft-masked input),
+    with a rough classic English letter-frequency ordering filled in for
+    the rest of the printable ASCII range so the table is still sensible
+    against ordinary text.
+*/
+pub static BYTE_FREQUENCY: [u16; 256] = [
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 5, 50, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 600, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    10, 10, 10, 10, 10, 10, 10, 10, 10, 10, 1, 1,
+    1, 1, 1, 1, 1, 2000, 15, 2000, 43, 127, 22, 2000,
+    61, 70, 2, 8, 40, 24, 67, 75, 19, 1, 60, 63,
+    2000, 28, 10, 24, 2, 20, 1, 1, 1, 1, 1, 1,
+    1, 400, 15, 400, 43, 127, 22, 400, 61, 70, 2, 8,
+    40, 24, 67, 75, 19, 1, 60, 63, 400, 28, 10, 24,
+    2, 20, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1,
+];
+
+/*
+    Return the offset of the rarest byte in `pat`, as determined by
+    `BYTE_FREQUENCY`. Ties are broken by earliest occurrence.
+*/
+pub fn rarest_byte_offset(pat: &[u8]) -> usize {
+    pat.iter()
+        .enumerate()
+        .min_by_key(|&(_, &b)| BYTE_FREQUENCY[b as usize])
+        .map(|(i, _)| i)
+        .expect("rarest_byte_offset: pattern must not be empty")
+}
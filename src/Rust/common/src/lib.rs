@@ -5,5 +5,8 @@
     them.
 */
 
+pub mod alphabet;
+pub mod cache;
+pub mod freq;
 pub mod input;
 pub mod run;
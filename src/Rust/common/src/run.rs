@@ -12,9 +12,39 @@
     init functions and primary functions.
 */
 
+use crate::cache::{
+    load_approx_pattern_data, load_pattern_data, save_approx_pattern_data,
+    save_pattern_data,
+};
 use crate::input::*;
+use memchr::memchr_iter;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use rayon::ThreadPool;
+use std::fs::File;
+use std::io::{BufReader, Read};
 use std::time::Instant;
 
+/*
+    Parallel execution across sequences is opt-in via the `PARALLEL`
+    environment variable. When it's set, its value is parsed as the number of
+    threads to use; an empty or unparseable value falls back to rayon's
+    default (one thread per core). Returns `None` when parallel execution
+    hasn't been requested, in which case callers fall back to the original
+    single-threaded loop.
+*/
+fn parallel_pool() -> Option<ThreadPool> {
+    let requested = std::env::var("PARALLEL").ok()?;
+    let mut builder = rayon::ThreadPoolBuilder::new();
+
+    if let Ok(n) = requested.parse::<usize>() {
+        if n > 0 {
+            builder = builder.num_threads(n);
+        }
+    }
+
+    Some(builder.build().expect("failed to build rayon thread pool"))
+}
+
 // `WordType` is used by shift-or. It's defined here so it can be used in the
 // enum, below.
 pub type WordType = u64;
@@ -26,13 +56,78 @@ pub enum PatternData {
     PatternIntVec(Vec<i32>),
     PatternWord(WordType),
     PatternWordVec(Vec<WordType>),
+    // Opt-in rare-byte prefilter: the byte value chosen as the anchor, and
+    // its offset within the pattern. An algorithm's init function pushes
+    // this (see `common::freq::rarest_byte_offset`) to have `run()` use
+    // `memchr` to jump between candidate windows instead of handing the
+    // whole sequence to `code` directly.
+    PatternAnchor(u8, usize),
+    // A rare byte within the pattern, and its offset, for an algorithm that
+    // wants to drive its own `memchr` jumps from inside its own search loop
+    // rather than have `run()` wrap it in `run_anchored` (see
+    // `boyer_moore`, which uses this to skip straight to candidate
+    // alignments while still falling back to its own good-suffix/
+    // bad-character verification once aligned).
+    PatternRareByte(u8, usize),
+    // A 256-entry byte-to-class lookup table, as built by
+    // `common::alphabet::ByteClasses`, paired with the class count. An
+    // algorithm packs this in when it has compressed its DFA/shift-table
+    // rows down to one column per equivalence class instead of one per byte.
+    PatternByteClasses(Vec<u8>, usize),
+    // A 256-entry byte-to-byte lookup table, as built by
+    // `common::alphabet::case_fold_table`: the ASCII-lowercase form of each
+    // byte when the `CASE_INSENSITIVE` flag is set, or the identity table
+    // otherwise. `kmp` indexes a sequence byte through this before
+    // comparing it against the (already-folded) pattern.
+    PatternFoldTable(Vec<u8>),
+}
+
+/*
+    Given an anchor byte/offset pair and the pattern length `m`, scan
+    `sequence` for occurrences of the anchor byte (via `memchr`) and, for
+    each one found at index `i`, invoke `code` against the bounds-checked
+    window of length `m` starting at `i - offset`, summing up the matches
+    it reports. This assumes `code` reports at most one match per
+    pattern-length window, which holds for every exact-matching algorithm
+    in this suite.
+*/
+fn run_anchored(
+    code: &Algorithm,
+    pat_data: &[PatternData],
+    sequence: &[u8],
+    anchor_byte: u8,
+    offset: usize,
+    m: usize,
+) -> i32 {
+    let n = sequence.len();
+    let mut matches: i32 = 0;
+
+    for i in memchr_iter(anchor_byte, sequence) {
+        if i < offset {
+            continue;
+        }
+        let start = i - offset;
+        if start + m > n {
+            continue;
+        }
+
+        let found = code(pat_data, &sequence[start..start + m]);
+        if found < 0 {
+            return found;
+        }
+        matches += found;
+    }
+
+    matches
 }
 
 // A type alias for the signature of the single-pattern matching algorithms.
-type Algorithm = dyn Fn(&[PatternData], &[u8]) -> i32;
+// `Sync` is required so that `code` can be called concurrently from multiple
+// threads in parallel mode.
+type Algorithm = dyn Fn(&[PatternData], &[u8]) -> i32 + Sync;
 // A type alias for the signature of the single-pattern initialization
 // functions.
-type Initializer = dyn Fn(&[u8]) -> Vec<PatternData>;
+type Initializer = dyn Fn(&[u8]) -> Vec<PatternData> + Sync;
 
 /*
    This is the "runner" routine. It takes a pointer to the code that
@@ -83,9 +178,16 @@ pub fn run(
     // `code` function pointer will return the number of matches found, which
     // will be compared to the table of answers for that pattern. Report any
     // mismatches.
+    let pool = parallel_pool();
     let start_time = Instant::now();
     let mut return_code: i32 = 0;
 
+    // Opt-in automaton cache, in the same style as the `PARALLEL` toggle
+    // above: when `AUTOMATON_CACHE` names a directory, a pattern's
+    // preprocessed data is loaded from there if present, and written
+    // there after `init` builds it otherwise. See `common::cache`.
+    let cache_dir = std::env::var("AUTOMATON_CACHE").ok();
+
     // Convert the patterns and sequences to `u8` (byte) arrays. Do this here
     // so that it isn't repeated in the for-loops.
     let patterns: Vec<&[u8]> =
@@ -94,10 +196,77 @@ pub fn run(
         sequences_data.iter().map(|s| s.as_bytes()).collect();
 
     for (pattern, pat_bytes) in patterns.iter().enumerate() {
-        let pat_data = init(pat_bytes);
+        // Run one sequence against this pattern, dispatching to the
+        // rare-byte prefilter wrapper when the pattern opted into it.
+        let run_one = |pat_data: &[PatternData], seq_bytes: &[u8]| -> i32 {
+            let anchor = pat_data.iter().find_map(|pd| match pd {
+                PatternData::PatternAnchor(byte, offset) => {
+                    Some((*byte, *offset))
+                }
+                _ => None,
+            });
 
-        for (sequence, seq_bytes) in sequences.iter().enumerate() {
-            let matches = code(&pat_data, seq_bytes);
+            match anchor {
+                Some((anchor_byte, offset)) => run_anchored(
+                    code,
+                    pat_data,
+                    seq_bytes,
+                    anchor_byte,
+                    offset,
+                    pat_bytes.len(),
+                ),
+                None => code(pat_data, seq_bytes),
+            }
+        };
+
+        // Load the pattern's automaton from the cache directory when one is
+        // configured and a matching file is present, otherwise build it with
+        // `init` and (when caching is enabled) save it for next time.
+        let get_pat_data = || -> Vec<PatternData> {
+            match &cache_dir {
+                Some(dir) => match load_pattern_data(dir, name, pat_bytes) {
+                    Some(data) => data,
+                    None => {
+                        let data = init(pat_bytes);
+                        if let Err(e) =
+                            save_pattern_data(dir, name, pat_bytes, &data)
+                        {
+                            eprintln!(
+                                "warning: failed to write automaton cache: {}",
+                                e
+                            );
+                        }
+                        data
+                    }
+                },
+                None => init(pat_bytes),
+            }
+        };
+
+        // In parallel mode, each worker thread re-runs `init` (or re-checks
+        // the cache) to get its own copy of the pattern data rather than
+        // sharing one across threads; for algorithms like `regexp` whose
+        // real state lives in a thread-local, this is what actually
+        // recompiles it on that thread.
+        let results: Vec<i32> = match &pool {
+            Some(pool) => pool.install(|| {
+                sequences
+                    .par_iter()
+                    .map_init(get_pat_data, |pat_data, seq_bytes| {
+                        run_one(pat_data, seq_bytes)
+                    })
+                    .collect()
+            }),
+            None => {
+                let pat_data = get_pat_data();
+                sequences
+                    .iter()
+                    .map(|seq_bytes| run_one(&pat_data, seq_bytes))
+                    .collect()
+            }
+        };
+
+        for (sequence, &matches) in results.iter().enumerate() {
             // If there was an error in the actual algorithm, `matches` will be
             // <0.
             if matches < 0 {
@@ -124,6 +293,10 @@ pub fn run(
     let elapsed = start_time.elapsed();
     println!("language: rust\nalgorithm: {}", &name);
     println!("runtime: {:.8}", elapsed.as_secs_f64());
+    println!(
+        "threads: {}",
+        pool.as_ref().map_or(1, |p| p.current_num_threads())
+    );
 
     return_code
 }
@@ -139,20 +312,40 @@ pub enum MultiPatternData<T> {
     PatternIntVecVec(Vec<Vec<i32>>),
     PatternUsizeVec(Vec<usize>),
     PatternTypeVec(Vec<T>),
+    // See `PatternData::PatternByteClasses`: a 256-entry byte-to-class
+    // lookup table and the class count, for algorithms that compress their
+    // state rows to one column per equivalence class.
+    PatternByteClasses(Vec<u8>, usize),
+    // The distinct depth-1 bytes (pattern-prefix bytes) an algorithm can use
+    // to skip ahead while parked in its start state, or an empty vector when
+    // the prefilter is disabled. See `aho_corasick`'s `AC_PREFILTER` flag.
+    PatternStartBytes(Vec<u8>),
+    // Per-state output sets, as plain pattern-index vectors rather than the
+    // `Set` type `aho_corasick` builds its automaton against -- for
+    // algorithms (like `teddy`'s automaton fallback) that need an
+    // Aho-Corasick-style output function but whose `T` is already spoken
+    // for by something else (e.g. the owned pattern bytes).
+    PatternUsizeVecVec(Vec<Vec<usize>>),
+    // See `PatternData::PatternFoldTable`: the ASCII case-fold lookup table
+    // `aho_corasick` indexes a sequence byte through, before indexing the
+    // byte-class table, when the `CASE_INSENSITIVE` flag is set.
+    PatternFoldTable(Vec<u8>),
 }
 
 // A type alias for the signature of the multi-pattern matching algorithms.
-type MPAlgorithm<T> = dyn Fn(&[MultiPatternData<T>], &[u8]) -> Vec<u32>;
+// `Sync` is required so that `code` can be called concurrently from multiple
+// threads in parallel mode.
+type MPAlgorithm<T> = dyn Fn(&[MultiPatternData<T>], &[u8]) -> Vec<u32> + Sync;
 // A type alias for the signature of the single-pattern initialization
 // functions.
-type MPInitializer<T> = dyn Fn(&[&[u8]]) -> Vec<MultiPatternData<T>>;
+type MPInitializer<T> = dyn Fn(&[&[u8]]) -> Vec<MultiPatternData<T>> + Sync;
 
 /*
    This is the "runner" routine for multi-pattern algorithms. The signature is
    identical to `run`, above, except for the generic type specification that is
    passed through to the MPInitializer and MPAlgorithm types.
 */
-pub fn run_multi<T>(
+pub fn run_multi<T: Send>(
     init: &MPInitializer<T>,
     code: &MPAlgorithm<T>,
     name: &str,
@@ -185,6 +378,7 @@ pub fn run_multi<T>(
     // `code` function pointer will return the number of matches found, which
     // will be compared to the table of answers for that pattern. Report any
     // mismatches.
+    let pool = parallel_pool();
     let start_time = Instant::now();
     let mut return_code: i32 = 0;
 
@@ -195,15 +389,28 @@ pub fn run_multi<T>(
     let sequences: Vec<&[u8]> =
         sequences_data.iter().map(|s| s.as_bytes()).collect();
 
-    // Initialize the multi-patterns structure.
-    let pat_data = init(&patterns);
-
-    for (sequence, sequence_str) in sequences.iter().enumerate() {
-        // Here, we don't iterate over the patterns. We just call the matching
-        // function and pass it the pattern-data structure set up in the init
-        // call above.
-        let matches = code(&pat_data, sequence_str);
+    // In parallel mode, each worker thread re-runs `init` to build its own
+    // copy of the multi-pattern structure rather than sharing one across
+    // threads.
+    let results: Vec<Vec<u32>> = match &pool {
+        Some(pool) => pool.install(|| {
+            sequences
+                .par_iter()
+                .map_init(|| init(&patterns), |pat_data, seq_bytes| {
+                    code(pat_data, seq_bytes)
+                })
+                .collect()
+        }),
+        None => {
+            let pat_data = init(&patterns);
+            sequences
+                .iter()
+                .map(|seq_bytes| code(&pat_data, seq_bytes))
+                .collect()
+        }
+    };
 
+    for (sequence, matches) in results.iter().enumerate() {
         if let Some(ref answers) = answers_data {
             for pattern in 0..patterns_data.len() {
                 if matches[pattern] != answers[pattern][sequence] {
@@ -225,6 +432,296 @@ pub fn run_multi<T>(
     let elapsed = start_time.elapsed();
     println!("language: rust\nalgorithm: {}", &name);
     println!("runtime: {:.8}", elapsed.as_secs_f64());
+    println!(
+        "threads: {}",
+        pool.as_ref().map_or(1, |p| p.current_num_threads())
+    );
+
+    return_code
+}
+
+/*
+    Span-reporting matching, as an alternative to the occurrence-counting
+    `run()`/`run_multi()` above. Rather than a plain match count, an
+    algorithm opting into this reports every match as a `MatchSpan`
+    (which pattern, and its half-open byte range), under one of three
+    semantics:
+
+        * Standard - every occurrence is reported, including ones that
+          overlap each other. This is the semantics `run()`/`run_multi()`
+          have always had, just surfaced as spans instead of a count.
+        * LeftmostFirst - matches don't overlap; when several patterns
+          could match starting at the same position, the one inserted
+          earliest (lowest index) wins.
+        * LeftmostLongest - matches don't overlap; the longest match
+          starting at the leftmost available position wins.
+
+    Like `run()`/`run_multi()`, an answers file is optional; when given,
+    it's checked against the per-pattern occurrence count obtained by
+    counting the spans reported for that pattern, so spans are validated
+    the same way every other algorithm in this project is.
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MatchKind {
+    Standard,
+    LeftmostFirst,
+    LeftmostLongest,
+}
+
+impl MatchKind {
+    fn parse(s: &str) -> Option<MatchKind> {
+        match s {
+            "standard" => Some(MatchKind::Standard),
+            "leftmost-first" => Some(MatchKind::LeftmostFirst),
+            "leftmost-longest" => Some(MatchKind::LeftmostLongest),
+            _ => None,
+        }
+    }
+}
+
+// A single reported occurrence: which pattern matched (by index into the
+// patterns given on the command line), and the half-open byte range
+// `[start, end)` within the sequence it matched at.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MatchSpan {
+    pub pattern: usize,
+    pub start: usize,
+    pub end: usize,
+}
+
+// A type alias for the signature of span-reporting multi-pattern matching
+// algorithms.
+type MPSpanAlgorithm<T> =
+    dyn Fn(&[MultiPatternData<T>], &[u8], MatchKind) -> Vec<MatchSpan> + Sync;
+
+/*
+    The span-reporting counterpart to `run_multi`: same pattern/sequence
+    set-up, but `code` is handed a `MatchKind` and returns match spans
+    instead of a per-pattern count.
+*/
+pub fn run_multi_spans<T: Send>(
+    init: &MPInitializer<T>,
+    code: &MPSpanAlgorithm<T>,
+    name: &str,
+    argv: Vec<String>,
+) -> i32 {
+    let argc = argv.len();
+    if !(4..=5).contains(&argc) {
+        eprintln!(
+            "Usage: {} <standard|leftmost-first|leftmost-longest> <sequences> <patterns> [ <answers> ]",
+            &argv[0]
+        );
+
+        return -1;
+    }
+
+    let kind = match MatchKind::parse(&argv[1]) {
+        Some(kind) => kind,
+        None => {
+            eprintln!("Unknown match kind: {}", &argv[1]);
+
+            return -1;
+        }
+    };
+
+    let sequences_data: Vec<String> = read_sequences(&argv[2]);
+    let patterns_data: Vec<String> = read_patterns(&argv[3]);
+    let answers_data: Option<Vec<Vec<u32>>> = if argc == 5 {
+        Some(read_answers(&argv[4]))
+    } else {
+        None
+    };
+
+    if let Some(ref answers) = answers_data {
+        if answers.len() != patterns_data.len() {
+            eprintln!("Count mismatch between patterns file and answers file");
+
+            return -1;
+        }
+    }
+
+    let pool = parallel_pool();
+    let start_time = Instant::now();
+
+    let patterns: Vec<&[u8]> =
+        patterns_data.iter().map(|p| p.as_bytes()).collect();
+    let sequences: Vec<&[u8]> =
+        sequences_data.iter().map(|s| s.as_bytes()).collect();
+
+    let results: Vec<Vec<MatchSpan>> = match &pool {
+        Some(pool) => pool.install(|| {
+            sequences
+                .par_iter()
+                .map_init(
+                    || init(&patterns),
+                    |pat_data, seq_bytes| code(pat_data, seq_bytes, kind),
+                )
+                .collect()
+        }),
+        None => {
+            let pat_data = init(&patterns);
+            sequences
+                .iter()
+                .map(|seq_bytes| code(&pat_data, seq_bytes, kind))
+                .collect()
+        }
+    };
+
+    // If answers were provided, check the per-pattern occurrence count
+    // obtained by counting each pattern's reported spans, the same way
+    // `run_multi` checks the count its algorithms return directly.
+    let mut return_code: i32 = 0;
+    if let Some(ref answers) = answers_data {
+        for (sequence, spans) in results.iter().enumerate() {
+            for pattern in 0..patterns_data.len() {
+                let count =
+                    spans.iter().filter(|s| s.pattern == pattern).count() as u32;
+                if count != answers[pattern][sequence] {
+                    eprintln!(
+                        "Pattern {} mismatch against sequence {} ({} != {})",
+                        pattern + 1,
+                        sequence + 1,
+                        count,
+                        answers[pattern][sequence]
+                    );
+
+                    return_code += 1;
+                }
+            }
+        }
+    }
+
+    let total_matches: usize = results.iter().map(|spans| spans.len()).sum();
+
+    let elapsed = start_time.elapsed();
+    println!("language: rust\nalgorithm: {}({:?})", &name, kind);
+    println!("runtime: {:.8}", elapsed.as_secs_f64());
+    println!(
+        "threads: {}",
+        pool.as_ref().map_or(1, |p| p.current_num_threads())
+    );
+    println!("matches: {}", total_matches);
+
+    return_code
+}
+
+// A type alias for the signature of span-reporting single-pattern matching
+// algorithms.
+type SpanAlgorithm =
+    dyn Fn(&[PatternData], &[u8], MatchKind) -> Vec<MatchSpan> + Sync;
+
+/*
+    The span-reporting counterpart to `run`, for single-pattern algorithms
+    like `kmp`: same pattern/sequence set-up, but `code` is handed a
+    `MatchKind` and returns match spans instead of a count.
+*/
+pub fn run_spans(
+    init: &Initializer,
+    code: &SpanAlgorithm,
+    name: &str,
+    argv: Vec<String>,
+) -> i32 {
+    let argc = argv.len();
+    if !(4..=5).contains(&argc) {
+        eprintln!(
+            "Usage: {} <standard|leftmost-first|leftmost-longest> <sequences> <patterns> [ <answers> ]",
+            &argv[0]
+        );
+
+        return -1;
+    }
+
+    let kind = match MatchKind::parse(&argv[1]) {
+        Some(kind) => kind,
+        None => {
+            eprintln!("Unknown match kind: {}", &argv[1]);
+
+            return -1;
+        }
+    };
+
+    let sequences_data: Vec<String> = read_sequences(&argv[2]);
+    let patterns_data: Vec<String> = read_patterns(&argv[3]);
+    let answers_data: Option<Vec<Vec<u32>>> = if argc == 5 {
+        Some(read_answers(&argv[4]))
+    } else {
+        None
+    };
+
+    if let Some(ref answers) = answers_data {
+        if answers.len() != patterns_data.len() {
+            eprintln!("Count mismatch between patterns file and answers file");
+
+            return -1;
+        }
+    }
+
+    let pool = parallel_pool();
+    let start_time = Instant::now();
+
+    let patterns: Vec<&[u8]> =
+        patterns_data.iter().map(|p| p.as_bytes()).collect();
+    let sequences: Vec<&[u8]> =
+        sequences_data.iter().map(|s| s.as_bytes()).collect();
+
+    // As in `run()`, loop over each pattern in the patterns file in turn;
+    // `code` only ever knows about the single pattern it was initialized
+    // with, so the per-pattern results are just summed into one grand
+    // total rather than tagged by an external pattern index.
+    let mut total_matches: usize = 0;
+    let mut return_code: i32 = 0;
+    for (pattern, pat_bytes) in patterns.iter().enumerate() {
+        let results: Vec<Vec<MatchSpan>> = match &pool {
+            Some(pool) => pool.install(|| {
+                sequences
+                    .par_iter()
+                    .map_init(
+                        || init(pat_bytes),
+                        |pat_data, seq_bytes| code(pat_data, seq_bytes, kind),
+                    )
+                    .collect()
+            }),
+            None => {
+                let pat_data = init(pat_bytes);
+                sequences
+                    .iter()
+                    .map(|seq_bytes| code(&pat_data, seq_bytes, kind))
+                    .collect()
+            }
+        };
+
+        // If an answers file was provided, check the per-sequence
+        // occurrence count obtained by counting this pattern's reported
+        // spans, the same way `run()` checks the count its algorithm
+        // returns directly.
+        if let Some(ref answers) = answers_data {
+            for (sequence, spans) in results.iter().enumerate() {
+                let count = spans.len() as u32;
+                if count != answers[pattern][sequence] {
+                    eprintln!(
+                        "Pattern {} mismatch against sequence {} ({} != {})",
+                        pattern + 1,
+                        sequence + 1,
+                        count,
+                        answers[pattern][sequence]
+                    );
+
+                    return_code += 1;
+                }
+            }
+        }
+
+        total_matches += results.iter().map(|spans| spans.len()).sum::<usize>();
+    }
+
+    let elapsed = start_time.elapsed();
+    println!("language: rust\nalgorithm: {}({:?})", &name, kind);
+    println!("runtime: {:.8}", elapsed.as_secs_f64());
+    println!(
+        "threads: {}",
+        pool.as_ref().map_or(1, |p| p.current_num_threads())
+    );
+    println!("matches: {}", total_matches);
 
     return_code
 }
@@ -234,13 +731,26 @@ pub fn run_multi<T>(
 pub enum ApproxPatternData {
     PatternIntVecVec(Vec<Vec<i32>>),
     PatternUsize(usize),
+    PatternWord(WordType),
+    PatternWordVec(Vec<WordType>),
+    PatternWordVecVec(Vec<Vec<WordType>>),
+    // See `PatternData::PatternByteClasses`: a 256-entry byte-to-class
+    // lookup table and the class count, for algorithms that compress their
+    // DFA rows to one column per equivalence class.
+    PatternByteClasses(Vec<u8>, usize),
+    // A sparse DFA transition table: one `(class, next_state)` pair per
+    // non-FAIL transition in each state, sorted by class, in place of a
+    // dense `num_classes`-wide row. See `dfa_gap_sparse`.
+    PatternSparseDfa(Vec<Vec<(u8, i32)>>),
 }
 
 // A type alias for the signature of the approximate matching algorithms.
-type AMAlgorithm = dyn Fn(&[ApproxPatternData], &[u8]) -> i32;
+// `Sync` is required so that `code` can be called concurrently from multiple
+// threads in parallel mode.
+type AMAlgorithm = dyn Fn(&[ApproxPatternData], &[u8]) -> i32 + Sync;
 // A type alias for the signature of the approximate matching initialization
 // functions.
-type AMInitializer = dyn Fn(&[u8], u32) -> Vec<ApproxPatternData>;
+type AMInitializer = dyn Fn(&[u8], u32) -> Vec<ApproxPatternData> + Sync;
 
 /*
    This is the "runner" routine for approximate matching algorithms. The
@@ -290,9 +800,13 @@ pub fn run_approx(
     // `code` function pointer will return the number of matches found, which
     // will be compared to the table of answers for that pattern. Report any
     // mismatches.
+    let pool = parallel_pool();
     let start_time = Instant::now();
     let mut return_code: i32 = 0;
 
+    // Opt-in automaton cache; see the identical toggle in `run()`.
+    let cache_dir = std::env::var("AUTOMATON_CACHE").ok();
+
     // Convert the patterns and sequences to `u8` (byte) arrays. Do this here
     // so that it isn't repeated in the for-loops.
     let patterns: Vec<&[u8]> =
@@ -301,10 +815,54 @@ pub fn run_approx(
         sequences_data.iter().map(|s| s.as_bytes()).collect();
 
     for (pattern, pat_bytes) in patterns.iter().enumerate() {
-        let pat_data = init(pat_bytes, k);
+        // Load the pattern's automaton from the cache directory when one is
+        // configured and a matching file is present, otherwise build it with
+        // `init` and (when caching is enabled) save it for next time.
+        let get_pat_data = || -> Vec<ApproxPatternData> {
+            match &cache_dir {
+                Some(dir) => match load_approx_pattern_data(dir, name, pat_bytes, k) {
+                    Some(data) => data,
+                    None => {
+                        let data = init(pat_bytes, k);
+                        if let Err(e) = save_approx_pattern_data(
+                            dir, name, pat_bytes, k, &data,
+                        ) {
+                            eprintln!(
+                                "warning: failed to write automaton cache: {}",
+                                e
+                            );
+                        }
+                        data
+                    }
+                },
+                None => init(pat_bytes, k),
+            }
+        };
 
-        for (sequence, seq_bytes) in sequences.iter().enumerate() {
-            let matches = code(&pat_data, seq_bytes);
+        // In parallel mode, each worker thread re-runs `init` (or re-checks
+        // the cache) to get its own copy of the pattern data rather than
+        // sharing one across threads; for the PCRE2-based `regexp` tool,
+        // whose real state lives in a thread-local, this is what actually
+        // recompiles the regex on that thread.
+        let results: Vec<i32> = match &pool {
+            Some(pool) => pool.install(|| {
+                sequences
+                    .par_iter()
+                    .map_init(get_pat_data, |pat_data, seq_bytes| {
+                        code(pat_data, seq_bytes)
+                    })
+                    .collect()
+            }),
+            None => {
+                let pat_data = get_pat_data();
+                sequences
+                    .iter()
+                    .map(|seq_bytes| code(&pat_data, seq_bytes))
+                    .collect()
+            }
+        };
+
+        for (sequence, &matches) in results.iter().enumerate() {
             // If there was an error in the actual algorithm, `matches` will be
             // <0.
             if matches < 0 {
@@ -331,6 +889,159 @@ pub fn run_approx(
     let elapsed = start_time.elapsed();
     println!("language: rust\nalgorithm: {}({})", &name, k);
     println!("runtime: {:.8}", elapsed.as_secs_f64());
+    println!(
+        "threads: {}",
+        pool.as_ref().map_or(1, |p| p.current_num_threads())
+    );
 
     return_code
 }
+
+/*
+    Streaming search over a `Read`, as an alternative to `run()`/
+    `run_multi()` above, which load the whole sequence into memory before
+    matching. Here the sequence argument names a file that is read through
+    in fixed-size chunks via a `BufReader`, with the algorithm's running
+    state carried from one chunk to the next, so arbitrarily large
+    FASTA-style input can be scanned with memory bounded by the chunk size
+    rather than the input size.
+
+    Unlike `run()`/`run_multi()`, there is exactly one sequence per
+    invocation (the one being streamed), and no parallel mode: the whole
+    point is a single sequence too large to hold in memory, so there is
+    nothing to divide across sequences the way `PARALLEL` does.
+*/
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+// A type alias for the signature of a multi-pattern streaming algorithm: it
+// is handed one chunk of the sequence plus the automaton state left over
+// from the previous chunk (0 for the first), and returns the updated state
+// along with the match counts found within that chunk.
+type MPStreamAlgorithm<T> =
+    dyn Fn(&[MultiPatternData<T>], &[u8], usize) -> (usize, Vec<u32>) + Sync;
+
+/*
+    Multi-pattern streaming runner (for `aho_corasick`-style algorithms,
+    whose state between chunks is a single automaton state index).
+*/
+pub fn run_multi_stream<T: Send>(
+    init: &MPInitializer<T>,
+    code: &MPStreamAlgorithm<T>,
+    name: &str,
+    argv: Vec<String>,
+) -> i32 {
+    let argc = argv.len();
+    if argc != 3 {
+        eprintln!("Usage: {} <sequence-file> <patterns>", &argv[0]);
+
+        return -1;
+    }
+
+    let patterns_data: Vec<String> = read_patterns(&argv[2]);
+    let patterns: Vec<&[u8]> = patterns_data.iter().map(|p| p.as_bytes()).collect();
+    let pat_data = init(&patterns);
+
+    let file = match File::open(&argv[1]) {
+        Ok(file) => file,
+        Err(err) => panic!("{}: File open error: {:?}", &argv[1], err),
+    };
+    let mut reader = BufReader::new(file);
+
+    let start_time = Instant::now();
+    let mut state: usize = 0;
+    let mut matches: Vec<u32> = vec![0; patterns.len()];
+    let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(n) => n,
+            Err(err) => panic!("{}: Error reading sequence: {:?}", &argv[1], err),
+        };
+        if n == 0 {
+            break;
+        }
+
+        let (new_state, chunk_matches) = code(&pat_data, &buffer[..n], state);
+        state = new_state;
+        for (total, found) in matches.iter_mut().zip(chunk_matches) {
+            *total += found;
+        }
+    }
+
+    let elapsed = start_time.elapsed();
+    println!("language: rust\nalgorithm: {}", &name);
+    println!("runtime: {:.8}", elapsed.as_secs_f64());
+    println!("threads: 1");
+    println!("matches: {}", matches.iter().sum::<u32>());
+
+    0
+}
+
+// A type alias for the signature of a single-pattern streaming algorithm: it
+// is handed one chunk of the sequence plus the KMP-style state left over
+// from the previous chunk (0 for the first), and returns the updated state
+// along with the match count found within that chunk.
+type StreamAlgorithm = dyn Fn(&[PatternData], &[u8], i32) -> (i32, u32) + Sync;
+
+/*
+    Single-pattern streaming runner (for `kmp`-style algorithms, whose
+    state between chunks is the failure-function index `i`; the sequence
+    cursor `j` need not be carried, since it is only ever used to index
+    into the sequence itself). As with `run()`, the patterns file may name
+    more than one pattern; since the sequence file can only be streamed
+    through once, it is reopened for each pattern in turn.
+*/
+pub fn run_stream(
+    init: &Initializer,
+    code: &StreamAlgorithm,
+    name: &str,
+    argv: Vec<String>,
+) -> i32 {
+    let argc = argv.len();
+    if argc != 3 {
+        eprintln!("Usage: {} <sequence-file> <patterns>", &argv[0]);
+
+        return -1;
+    }
+
+    let patterns_data: Vec<String> = read_patterns(&argv[2]);
+    let patterns: Vec<&[u8]> = patterns_data.iter().map(|p| p.as_bytes()).collect();
+
+    let start_time = Instant::now();
+    let mut total_matches: u32 = 0;
+
+    for pat_bytes in &patterns {
+        let pat_data = init(pat_bytes);
+
+        let file = match File::open(&argv[1]) {
+            Ok(file) => file,
+            Err(err) => panic!("{}: File open error: {:?}", &argv[1], err),
+        };
+        let mut reader = BufReader::new(file);
+
+        let mut state: i32 = 0;
+        let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+
+        loop {
+            let n = match reader.read(&mut buffer) {
+                Ok(n) => n,
+                Err(err) => panic!("{}: Error reading sequence: {:?}", &argv[1], err),
+            };
+            if n == 0 {
+                break;
+            }
+
+            let (new_state, chunk_matches) = code(&pat_data, &buffer[..n], state);
+            state = new_state;
+            total_matches += chunk_matches;
+        }
+    }
+
+    let elapsed = start_time.elapsed();
+    println!("language: rust\nalgorithm: {}", &name);
+    println!("runtime: {:.8}", elapsed.as_secs_f64());
+    println!("threads: 1");
+    println!("matches: {}", total_matches);
+
+    0
+}
@@ -3,23 +3,21 @@
     approximate string matching.
 */
 
+use common::alphabet::ByteClasses;
 use common::run::{run_approx, ApproxPatternData};
+use std::collections::{HashSet, VecDeque};
 use std::env;
 use std::process::exit;
 
-// Rather than implement a translation table for the four characters in the DNA
-// alphabet, for now just let the alphabet be the full ASCII range and only use
-// those four.
-const ASIZE: usize = 128;
-
 // The "fail" value is used to determine when to start over.
 const FAIL: i32 = -1;
 
 /*
     The ALPHABET values are used when setting up the transitions around the
-    "gap" states in the DFA. Since we're being lazy about translating ACGT to
-    0-3 and using an alphabet of 128 instead, this will save some time in loops
-    during the creation of the DFA.
+    "gap" states in the DFA. This is every byte that can legally appear in a
+    pattern; transitions for everything else fall out of the DFA rows being
+    compressed to one column per equivalence class (see
+    `common::alphabet::ByteClasses`) instead of one per possible byte.
 */
 const ALPHABET: &[usize] = &[65, 67, 71, 84];
 
@@ -28,17 +26,19 @@ fn create_dfa(
     m: usize,
     k: u32,
     dfa: &mut Vec<Vec<i32>>,
+    classes: &ByteClasses,
 ) -> usize {
     // We know that the number of states will be 1 + m + k(m - 1).
     let max_states: usize = 1 + m + k as usize * (m - 1);
 
-    // Allocate the DFA
+    // Allocate the DFA, with one column per equivalence class rather than
+    // one per possible byte.
     for _ in 0..max_states {
-        dfa.push(vec![FAIL; ASIZE]);
+        dfa.push(vec![FAIL; classes.num_classes()]);
     }
 
     // First step: set dfa[0][pattern[0]] = state(1)
-    dfa[0][pattern[0] as usize] = 1;
+    dfa[0][classes.class(pattern[0])] = 1;
 
     // Start `state` and `new_state` both at 1
     let mut state: usize = 1;
@@ -51,13 +51,13 @@ fn create_dfa(
         // Move `new_state` to the next place.
         new_state += 1;
         // The previous `state` maps to `new_state` on `pattern[i]`
-        dfa[state][pattern[i] as usize] = new_state as i32;
+        dfa[state][classes.class(pattern[i])] = new_state as i32;
         // `last_state` is used to control setting transitions for other values
         let mut last_state = state;
         for j in 1..=k {
             // For each of 1..k, we start a new state for which `pattern[i]`
             // maps to `new_state`.
-            dfa[(new_state + j as usize)][pattern[i] as usize] =
+            dfa[new_state + j as usize][classes.class(pattern[i])] =
                 new_state as i32;
             for n in ALPHABET {
                 if *n == pattern[i] as usize {
@@ -65,7 +65,8 @@ fn create_dfa(
                 }
                 // Every character that isn't `pattern[i]` needs to map
                 // `last_state` to this new state-value.
-                dfa[last_state][*n] = (new_state + j as usize) as i32;
+                dfa[last_state][classes.class(*n as u8)] =
+                    (new_state + j as usize) as i32;
             }
             // Shift `last_state` for the next iteration.
             last_state = new_state + j as usize;
@@ -80,22 +81,167 @@ fn create_dfa(
     state
 }
 
+/*
+    Minimize the gap DFA with Hopcroft's partition-refinement algorithm
+    before it is packed for `dfa_gap`. Many of the `1 + m + k(m - 1)` states
+    `create_dfa` builds -- especially the parallel "gap" states that track an
+    in-progress mismatch run -- turn out to be behaviorally identical, so
+    this pass can meaningfully shrink the table the search loop walks.
+
+    Hopcroft's algorithm is defined over *complete* DFAs, so FAIL
+    transitions are first redirected to an explicit dead state (index
+    `dfa.len()`) that loops to itself on every class. States start out
+    split into the single accepting state and everything else, then blocks
+    are repeatedly refined against a worklist of splitter blocks until none
+    can be split further; at that point every state in a block has
+    identical transitions (by block) for every class, so each block
+    collapses to one output state. The dead state's block is dropped from
+    the rebuilt table, with any transition that would land there translated
+    back to FAIL.
+
+    Returns the minimized transition table and the remapped terminal state.
+*/
+fn minimize_dfa(
+    dfa: &[Vec<i32>],
+    terminal: usize,
+    num_classes: usize,
+) -> (Vec<Vec<i32>>, usize) {
+    let dead = dfa.len();
+
+    // A total transition function, with `dead` standing in for FAIL.
+    let total = |state: usize, class: usize| -> usize {
+        if state == dead || dfa[state][class] == FAIL {
+            dead
+        } else {
+            dfa[state][class] as usize
+        }
+    };
+
+    // Initial partition: the accepting state by itself, and every other
+    // state (including the dead one) in a single block.
+    let mut partition: Vec<HashSet<usize>> = vec![
+        [terminal].into_iter().collect(),
+        (0..=dead).filter(|&s| s != terminal).collect(),
+    ];
+    let mut worklist: VecDeque<HashSet<usize>> =
+        partition.iter().cloned().collect();
+
+    while let Some(splitter) = worklist.pop_front() {
+        for class in 0..num_classes {
+            // X = every state whose transition on `class` lands in the
+            // splitter block.
+            let x: HashSet<usize> = (0..=dead)
+                .filter(|&s| splitter.contains(&total(s, class)))
+                .collect();
+            if x.is_empty() {
+                continue;
+            }
+
+            let mut next_partition = Vec::with_capacity(partition.len() + 1);
+            for block in partition.drain(..) {
+                let intersect: HashSet<usize> =
+                    block.intersection(&x).copied().collect();
+                let difference: HashSet<usize> =
+                    block.difference(&x).copied().collect();
+
+                if intersect.is_empty() || difference.is_empty() {
+                    next_partition.push(block);
+                    continue;
+                }
+
+                // `block` splits into two; if it was itself a pending
+                // splitter, both halves must take its place in the
+                // worklist, else only the smaller half needs to be added.
+                if let Some(pos) =
+                    worklist.iter().position(|pending| *pending == block)
+                {
+                    worklist.remove(pos);
+                    worklist.push_back(intersect.clone());
+                    worklist.push_back(difference.clone());
+                } else if intersect.len() <= difference.len() {
+                    worklist.push_back(intersect.clone());
+                } else {
+                    worklist.push_back(difference.clone());
+                }
+
+                next_partition.push(intersect);
+                next_partition.push(difference);
+            }
+            partition = next_partition;
+        }
+    }
+
+    // Map every original state to the index of the block it ended up in.
+    let mut state_to_block = vec![0usize; dead + 1];
+    for (i, block) in partition.iter().enumerate() {
+        for &state in block {
+            state_to_block[state] = i;
+        }
+    }
+    let dead_block = state_to_block[dead];
+
+    // Assign compact output indices to every surviving (non-dead) block,
+    // ordered by each block's lowest-numbered member so state 0 keeps
+    // output index 0.
+    let mut surviving: Vec<usize> =
+        (0..partition.len()).filter(|&b| b != dead_block).collect();
+    surviving.sort_by_key(|&b| *partition[b].iter().min().unwrap());
+    let mut block_to_new: Vec<Option<usize>> = vec![None; partition.len()];
+    for (new_idx, &block) in surviving.iter().enumerate() {
+        block_to_new[block] = Some(new_idx);
+    }
+
+    let mut minimized: Vec<Vec<i32>> =
+        vec![vec![FAIL; num_classes]; surviving.len()];
+    for (new_idx, &block) in surviving.iter().enumerate() {
+        // Every state in a settled block has identical transitions, so any
+        // representative member will do.
+        let rep = *partition[block].iter().next().unwrap();
+        for class in 0..num_classes {
+            let target_block = state_to_block[total(rep, class)];
+            minimized[new_idx][class] = match block_to_new[target_block] {
+                Some(idx) => idx as i32,
+                None => FAIL,
+            };
+        }
+    }
+
+    let new_terminal = block_to_new[state_to_block[terminal]].unwrap();
+
+    (minimized, new_terminal)
+}
+
 /*
     Initialize the DFA for the pattern and store the data in the packed form
     that will be passed to `dfa_gap` for each sequence being matched.
 */
 fn init_dfa_gap(pattern: &[u8], k: u32) -> Vec<ApproxPatternData> {
-    let mut pattern_data: Vec<ApproxPatternData> = Vec::with_capacity(3);
+    let mut pattern_data: Vec<ApproxPatternData> = Vec::with_capacity(4);
 
-    // Initialize the elements for the multi-patterns structure.
+    // Initialize the elements for the multi-patterns structure. The DFA rows
+    // are indexed by equivalence class (see `common::alphabet`): for a DNA
+    // pattern that's one class per base plus "other", instead of 128 columns.
+    let classes = ByteClasses::from_pattern(pattern);
     let mut dfa: Vec<Vec<i32>> = Vec::new();
     let m = pattern.len();
-    let terminal = create_dfa(pattern, m, k, &mut dfa);
+    let terminal = create_dfa(pattern, m, k, &mut dfa, &classes);
+
+    let pre_states = dfa.len();
+    let (dfa, terminal) = minimize_dfa(&dfa, terminal, classes.num_classes());
+    eprintln!(
+        "dfa_gap: minimized DFA from {} states to {} states",
+        pre_states,
+        dfa.len()
+    );
 
     // Pack the return structure.
     pattern_data.push(ApproxPatternData::PatternIntVecVec(dfa));
     pattern_data.push(ApproxPatternData::PatternUsize(terminal));
     pattern_data.push(ApproxPatternData::PatternUsize(m));
+    pattern_data.push(ApproxPatternData::PatternByteClasses(
+        classes.table(),
+        classes.num_classes(),
+    ));
 
     pattern_data
 }
@@ -118,6 +264,10 @@ fn dfa_gap(pat_data: &[ApproxPatternData], sequence: &[u8]) -> i32 {
         ApproxPatternData::PatternUsize(val) => val,
         _ => panic!("Incorrect value at pat_data slot 2"),
     };
+    let classes = match &pat_data[3] {
+        ApproxPatternData::PatternByteClasses(table, _) => table,
+        _ => panic!("Incorrect value at pat_data slot 3"),
+    };
 
     let mut matches = 0;
     let n = sequence.len();
@@ -129,8 +279,12 @@ fn dfa_gap(pat_data: &[ApproxPatternData], sequence: &[u8]) -> i32 {
         let mut state: usize = 0;
         let mut ch: usize = 0;
 
-        while (i + ch) < n && dfa[state][sequence[i + ch] as usize] != FAIL {
-            state = dfa[state][sequence[i + ch] as usize] as usize;
+        while (i + ch) < n {
+            let class = classes[sequence[i + ch] as usize] as usize;
+            if dfa[state][class] == FAIL {
+                break;
+            }
+            state = dfa[state][class] as usize;
             ch += 1;
         }
 
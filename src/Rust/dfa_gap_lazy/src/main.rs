@@ -0,0 +1,274 @@
+/*
+    Lazily-constructed variant of the DFA-Gap algorithm for approximate
+    string matching, in the style of the regex-automata `hybrid` lazy DFA.
+
+    Rather than materialize all `1 + m + k(m - 1)` states up front the way
+    `dfa_gap` does, this only ever builds the handful of states that a real
+    search actually visits: each semantic state (the pattern position last
+    matched, and how many tolerated mismatches have been spent since) is
+    assigned an integer id the first time it's reached, and each
+    `(state, byte-class)` transition is computed from the gap-matching rule
+    and memoized the first time it's needed. A cap on the number of cached
+    states bounds the memory a long run can accumulate; once it's hit, the
+    cache is cleared and rebuilt as needed, trading a little recomputation
+    for a fixed memory ceiling.
+
+    As with `regexp`, adding a crate-specific cache type to the shared
+    `ApproxPatternData` enum would have meant every tool in the suite taking
+    on this crate's internals. So the cache lives behind a `thread_local`,
+    exactly as `regexp` does for its compiled `Regex`, and `init_dfa_gap_lazy`
+    does nothing but (re)seed it for the current pattern.
+*/
+
+use common::alphabet::ByteClasses;
+use common::run::{run_approx, ApproxPatternData};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::process::exit;
+
+// The "fail" value, as in `dfa_gap`.
+const FAIL: i32 = -1;
+
+// The bytes that may fill a gap, as in `dfa_gap`.
+const ALPHABET: &[u8] = &[65, 67, 71, 84];
+
+// Once the cache has memoized this many distinct states, it's cleared and
+// allowed to rebuild from scratch. This bounds memory use for large
+// patterns/large k at the cost of some recomputation.
+const MAX_CACHED_STATES: usize = 4096;
+
+/*
+    A semantic DFA-Gap state, independent of any integer numbering: either
+    the initial state, "matched pattern[0..=i] exactly" (no mismatches
+    pending), or "working toward pattern[i], having already tolerated `gap`
+    consecutive non-matching bytes since pattern[i - 1]".
+*/
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+enum StateKey {
+    Start,
+    Matched(usize),
+    Gap(usize, usize),
+}
+
+/*
+    The lazy construction cache: the pattern and tolerance being searched
+    for, its byte classes, and the memo tables mapping semantic states to
+    integer ids and `(id, class)` pairs to the transition's resulting id (or
+    `FAIL`).
+*/
+struct LazyCache {
+    pattern: Vec<u8>,
+    k: usize,
+    classes: ByteClasses,
+    id_to_key: Vec<StateKey>,
+    key_to_id: HashMap<StateKey, usize>,
+    transitions: HashMap<(usize, usize), i32>,
+}
+
+impl LazyCache {
+    fn new(pattern: Vec<u8>, k: usize, classes: ByteClasses) -> LazyCache {
+        let mut cache = LazyCache {
+            pattern,
+            k,
+            classes,
+            id_to_key: Vec::new(),
+            key_to_id: HashMap::new(),
+            transitions: HashMap::new(),
+        };
+        // Always seed `Start` first, so it's guaranteed to be id 0 both
+        // initially and after any later eviction.
+        cache.id_for(StateKey::Start);
+
+        cache
+    }
+
+    /*
+        Look up (or assign, on first sight) the integer id for a semantic
+        state.
+    */
+    fn id_for(&mut self, key: StateKey) -> usize {
+        if let Some(&id) = self.key_to_id.get(&key) {
+            return id;
+        }
+
+        let id = self.id_to_key.len();
+        self.id_to_key.push(key);
+        self.key_to_id.insert(key, id);
+
+        id
+    }
+
+    /*
+        The gap-matching transition rule itself: on `class`, where does
+        `key` lead? This is the same recurrence `dfa_gap::create_dfa` bakes
+        into an explicit table; here it is evaluated on demand instead.
+    */
+    fn semantic_transition(
+        &self,
+        key: StateKey,
+        class: usize,
+    ) -> Option<StateKey> {
+        // As in `create_dfa`, only a byte of `ALPHABET` (or one sharing its
+        // class) can fill a gap; any other byte fails.
+        let gap_ok = ALPHABET.iter().any(|&b| self.classes.class(b) == class);
+        match key {
+            StateKey::Start => {
+                if class == self.classes.class(self.pattern[0]) {
+                    Some(StateKey::Matched(0))
+                } else {
+                    None
+                }
+            }
+            StateKey::Matched(i) => {
+                if i + 1 >= self.pattern.len() {
+                    // The fully-matched (terminal) state has no further
+                    // transitions.
+                    None
+                } else if class == self.classes.class(self.pattern[i + 1]) {
+                    Some(StateKey::Matched(i + 1))
+                } else if self.k >= 1 && gap_ok {
+                    Some(StateKey::Gap(i + 1, 1))
+                } else {
+                    None
+                }
+            }
+            StateKey::Gap(i, gap) => {
+                if class == self.classes.class(self.pattern[i]) {
+                    Some(StateKey::Matched(i))
+                } else if gap < self.k && gap_ok {
+                    Some(StateKey::Gap(i, gap + 1))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /*
+        If the cache has grown past its cap, drop everything and reseed
+        `Start`. Safe to call only between search windows (i.e. whenever
+        the caller's own `state` is about to be reset to `Start`'s id),
+        since it invalidates every previously-assigned id.
+    */
+    fn maybe_evict(&mut self) {
+        if self.id_to_key.len() > MAX_CACHED_STATES {
+            self.id_to_key.clear();
+            self.key_to_id.clear();
+            self.transitions.clear();
+            self.id_for(StateKey::Start);
+        }
+    }
+
+    /*
+        The memoized transition function used by the search loop: compute
+        (and cache) the result of `(state, class)` the first time it's
+        asked for.
+    */
+    fn transition(&mut self, state: usize, class: usize) -> i32 {
+        if let Some(&next) = self.transitions.get(&(state, class)) {
+            return next;
+        }
+
+        let key = self.id_to_key[state];
+        let next = match self.semantic_transition(key, class) {
+            Some(next_key) => self.id_for(next_key) as i32,
+            None => FAIL,
+        };
+        self.transitions.insert((state, class), next);
+
+        next
+    }
+
+    /*
+        Whether `state` is the fully-matched state for the last character of
+        the pattern.
+    */
+    fn is_terminal(&self, state: usize) -> bool {
+        matches!(
+            self.id_to_key.get(state),
+            Some(StateKey::Matched(i)) if i + 1 == self.pattern.len()
+        )
+    }
+}
+
+thread_local!(
+    static CACHE: RefCell<LazyCache> = RefCell::new(LazyCache::new(
+        Vec::new(),
+        0,
+        ByteClasses::from_pattern(&[]),
+    ))
+);
+
+/*
+    (Re)seed the thread-local lazy-construction cache for this pattern and
+    `k`. The only thing `dfa_gap_lazy` itself needs from `pattern_data` is
+    `m`, to know how far a window can extend.
+*/
+fn init_dfa_gap_lazy(pattern: &[u8], k: u32) -> Vec<ApproxPatternData> {
+    let classes = ByteClasses::from_pattern(pattern);
+
+    CACHE.with(|cell| {
+        *cell.borrow_mut() =
+            LazyCache::new(pattern.to_vec(), k as usize, classes);
+    });
+
+    vec![ApproxPatternData::PatternUsize(pattern.len())]
+}
+
+/*
+    Perform the DFA-Gap algorithm against the given sequence, building and
+    caching transitions on demand instead of walking a precomputed table.
+*/
+fn dfa_gap_lazy(pat_data: &[ApproxPatternData], sequence: &[u8]) -> i32 {
+    let m = match &pat_data[0] {
+        ApproxPatternData::PatternUsize(val) => *val,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+
+    let mut matches: i32 = 0;
+    let n = sequence.len();
+    let end = n - m;
+
+    CACHE.with(|cell| {
+        let mut cache = cell.borrow_mut();
+
+        for i in 0..=end {
+            // Only evict between windows: every id read during a window's
+            // own search has to stay valid for that whole window.
+            cache.maybe_evict();
+
+            let mut state: usize = 0; // `Start`'s id, by construction.
+            let mut ch: usize = 0;
+
+            while (i + ch) < n {
+                let class = cache.classes.class(sequence[i + ch]);
+                let next = cache.transition(state, class);
+                if next == FAIL {
+                    break;
+                }
+                state = next as usize;
+                ch += 1;
+            }
+
+            if cache.is_terminal(state) {
+                matches += 1;
+            }
+        }
+    });
+
+    matches
+}
+
+/*
+    All that is done here is call the run_approx() function with the values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_approx(
+        &init_dfa_gap_lazy,
+        &dfa_gap_lazy,
+        "dfa_gap_lazy",
+        argv,
+    ));
+}
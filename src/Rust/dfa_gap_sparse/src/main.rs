@@ -0,0 +1,278 @@
+/*
+    Sparse-transition variant of the DFA-Gap algorithm for approximate string
+    matching.
+
+    This builds the exact same minimized DFA as `dfa_gap` (see that crate for
+    the construction and minimization steps), but packs each state as a list
+    of `(class, next_state)` pairs covering only its non-FAIL transitions,
+    sorted by class, instead of a dense `num_classes`-wide row. Since each
+    DNA-pattern state has at most a handful of real transitions, this trades
+    an O(log num_classes) binary search per step for a much smaller table --
+    letting the harness benchmark the memory/speed tradeoff against the dense
+    form directly.
+*/
+
+use common::alphabet::ByteClasses;
+use common::run::{run_approx, ApproxPatternData};
+use std::collections::{HashSet, VecDeque};
+use std::env;
+use std::process::exit;
+
+// The "fail" value is used to determine when to start over.
+const FAIL: i32 = -1;
+
+// Every byte that can legally appear in a pattern; everything else falls out
+// of the equivalence-class compression (see `common::alphabet::ByteClasses`).
+const ALPHABET: &[usize] = &[65, 67, 71, 84];
+
+/*
+    Identical to `dfa_gap::create_dfa`: build the dense, class-compressed
+    transition table for the gap DFA.
+*/
+fn create_dfa(
+    pattern: &[u8],
+    m: usize,
+    k: u32,
+    dfa: &mut Vec<Vec<i32>>,
+    classes: &ByteClasses,
+) -> usize {
+    let max_states: usize = 1 + m + k as usize * (m - 1);
+
+    for _ in 0..max_states {
+        dfa.push(vec![FAIL; classes.num_classes()]);
+    }
+
+    dfa[0][classes.class(pattern[0])] = 1;
+
+    let mut state: usize = 1;
+    let mut new_state: usize = 1;
+
+    for i in 1..m {
+        new_state += 1;
+        dfa[state][classes.class(pattern[i])] = new_state as i32;
+        let mut last_state = state;
+        for j in 1..=k {
+            dfa[(new_state + j as usize)][classes.class(pattern[i])] =
+                new_state as i32;
+            for n in ALPHABET {
+                if *n == pattern[i] as usize {
+                    continue;
+                }
+                dfa[last_state][classes.class(*n as u8)] =
+                    (new_state + j as usize) as i32;
+            }
+            last_state = new_state + j as usize;
+        }
+        state = new_state;
+        new_state += k as usize;
+    }
+
+    state
+}
+
+/*
+    Identical to `dfa_gap::minimize_dfa`: collapse behaviorally-equivalent
+    states with Hopcroft's partition-refinement algorithm before the table is
+    packed. See that crate for the full explanation.
+*/
+fn minimize_dfa(
+    dfa: &[Vec<i32>],
+    terminal: usize,
+    num_classes: usize,
+) -> (Vec<Vec<i32>>, usize) {
+    let dead = dfa.len();
+
+    let total = |state: usize, class: usize| -> usize {
+        if state == dead || dfa[state][class] == FAIL {
+            dead
+        } else {
+            dfa[state][class] as usize
+        }
+    };
+
+    let mut partition: Vec<HashSet<usize>> = vec![
+        [terminal].into_iter().collect(),
+        (0..=dead).filter(|&s| s != terminal).collect(),
+    ];
+    let mut worklist: VecDeque<HashSet<usize>> =
+        partition.iter().cloned().collect();
+
+    while let Some(splitter) = worklist.pop_front() {
+        for class in 0..num_classes {
+            let x: HashSet<usize> = (0..=dead)
+                .filter(|&s| splitter.contains(&total(s, class)))
+                .collect();
+            if x.is_empty() {
+                continue;
+            }
+
+            let mut next_partition = Vec::with_capacity(partition.len() + 1);
+            for block in partition.drain(..) {
+                let intersect: HashSet<usize> =
+                    block.intersection(&x).copied().collect();
+                let difference: HashSet<usize> =
+                    block.difference(&x).copied().collect();
+
+                if intersect.is_empty() || difference.is_empty() {
+                    next_partition.push(block);
+                    continue;
+                }
+
+                if let Some(pos) =
+                    worklist.iter().position(|pending| *pending == block)
+                {
+                    worklist.remove(pos);
+                    worklist.push_back(intersect.clone());
+                    worklist.push_back(difference.clone());
+                } else if intersect.len() <= difference.len() {
+                    worklist.push_back(intersect.clone());
+                } else {
+                    worklist.push_back(difference.clone());
+                }
+
+                next_partition.push(intersect);
+                next_partition.push(difference);
+            }
+            partition = next_partition;
+        }
+    }
+
+    let mut state_to_block = vec![0usize; dead + 1];
+    for (i, block) in partition.iter().enumerate() {
+        for &state in block {
+            state_to_block[state] = i;
+        }
+    }
+    let dead_block = state_to_block[dead];
+
+    let mut surviving: Vec<usize> =
+        (0..partition.len()).filter(|&b| b != dead_block).collect();
+    surviving.sort_by_key(|&b| *partition[b].iter().min().unwrap());
+    let mut block_to_new: Vec<Option<usize>> = vec![None; partition.len()];
+    for (new_idx, &block) in surviving.iter().enumerate() {
+        block_to_new[block] = Some(new_idx);
+    }
+
+    let mut minimized: Vec<Vec<i32>> =
+        vec![vec![FAIL; num_classes]; surviving.len()];
+    for (new_idx, &block) in surviving.iter().enumerate() {
+        let rep = *partition[block].iter().next().unwrap();
+        for class in 0..num_classes {
+            let target_block = state_to_block[total(rep, class)];
+            minimized[new_idx][class] = match block_to_new[target_block] {
+                Some(idx) => idx as i32,
+                None => FAIL,
+            };
+        }
+    }
+
+    let new_terminal = block_to_new[state_to_block[terminal]].unwrap();
+
+    (minimized, new_terminal)
+}
+
+/*
+    Pack a dense, class-compressed transition table down to one
+    `(class, next_state)` pair per non-FAIL entry, per state. The pairs come
+    out already sorted by class, since they're read off the dense row in
+    increasing column order.
+*/
+fn to_sparse(dfa: &[Vec<i32>]) -> Vec<Vec<(u8, i32)>> {
+    dfa.iter()
+        .map(|row| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &next)| next != FAIL)
+                .map(|(class, &next)| (class as u8, next))
+                .collect()
+        })
+        .collect()
+}
+
+/*
+    Build the minimized gap DFA for the pattern, then repack it in sparse
+    form for `dfa_gap_sparse`.
+*/
+fn init_dfa_gap_sparse(pattern: &[u8], k: u32) -> Vec<ApproxPatternData> {
+    let mut pattern_data: Vec<ApproxPatternData> = Vec::with_capacity(4);
+
+    let classes = ByteClasses::from_pattern(pattern);
+    let mut dfa: Vec<Vec<i32>> = Vec::new();
+    let m = pattern.len();
+    let terminal = create_dfa(pattern, m, k, &mut dfa, &classes);
+    let (dfa, terminal) = minimize_dfa(&dfa, terminal, classes.num_classes());
+    let sparse = to_sparse(&dfa);
+
+    pattern_data.push(ApproxPatternData::PatternSparseDfa(sparse));
+    pattern_data.push(ApproxPatternData::PatternUsize(terminal));
+    pattern_data.push(ApproxPatternData::PatternUsize(m));
+    pattern_data.push(ApproxPatternData::PatternByteClasses(
+        classes.table(),
+        classes.num_classes(),
+    ));
+
+    pattern_data
+}
+
+/*
+    Perform the DFA-Gap algorithm against the given sequence, using a binary
+    search over each state's sparse transition list instead of a direct
+    dense-row lookup.
+*/
+fn dfa_gap_sparse(pat_data: &[ApproxPatternData], sequence: &[u8]) -> i32 {
+    let sparse = match &pat_data[0] {
+        ApproxPatternData::PatternSparseDfa(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+    let terminal = match &pat_data[1] {
+        ApproxPatternData::PatternUsize(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    };
+    let m = match &pat_data[2] {
+        ApproxPatternData::PatternUsize(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 2"),
+    };
+    let classes = match &pat_data[3] {
+        ApproxPatternData::PatternByteClasses(table, _) => table,
+        _ => panic!("Incorrect value at pat_data slot 3"),
+    };
+
+    let mut matches = 0;
+    let n = sequence.len();
+
+    let end = n - m;
+    for i in 0..=end {
+        let mut state: usize = 0;
+        let mut ch: usize = 0;
+
+        while (i + ch) < n {
+            let class = classes[sequence[i + ch] as usize];
+            match sparse[state].binary_search_by_key(&class, |&(c, _)| c) {
+                Ok(idx) => {
+                    state = sparse[state][idx].1 as usize;
+                    ch += 1;
+                }
+                Err(_) => break,
+            }
+        }
+
+        if state == *terminal {
+            matches += 1;
+        }
+    }
+
+    matches
+}
+
+/*
+    All that is done here is call the run_approx() function with the values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_approx(
+        &init_dfa_gap_sparse,
+        &dfa_gap_sparse,
+        "dfa_gap_sparse",
+        argv,
+    ));
+}
@@ -0,0 +1,120 @@
+/*
+    Span-reporting variant of the Knuth-Morris-Pratt single-pattern matcher.
+
+    `kmp` only ever returns an occurrence count. This variant instead
+    reports every match as a `(pattern, start, end)` span (`pattern` is
+    always 0, since KMP only ever searches for one pattern at a time),
+    under one of the three `common::run::MatchKind` semantics.
+
+    Because every match of a single fixed pattern has the same length m,
+    "leftmost-first" and "leftmost-longest" collapse to the same thing
+    here: there's never a choice between two candidates of different
+    length or insertion order to break a tie on, so both simply mean
+    "non-overlapping" -- report a match, then resume scanning right after
+    it instead of resuming mid-overlap the way `standard` (the existing
+    `kmp` behavior) does.
+
+    `build_next_table` is identical to `kmp`'s; see that crate for the
+    jump-table construction. `init_kmp_spans` matches `kmp`'s `init_kmp`
+    without the case folding: the pattern is used as given and no fold
+    table is packed.
+*/
+
+use common::run::{run_spans, MatchKind, MatchSpan, PatternData};
+use std::env;
+use std::process::exit;
+
+fn build_next_table(pat: &[u8], m: usize) -> Vec<i32> {
+    let mut next_table: Vec<i32> = vec![0; m + 1];
+    let mut i: usize = 0;
+    let mut j: i32 = -1;
+    next_table[0] = -1;
+
+    while i < m {
+        while j > -1 && pat[i] != pat[j as usize] {
+            j = next_table[j as usize];
+        }
+        i += 1;
+        j += 1;
+        if i < m && pat[i] == pat[j as usize] {
+            next_table[i] = next_table[j as usize];
+        } else {
+            next_table[i] = j;
+        }
+    }
+
+    next_table
+}
+
+fn init_kmp_spans(pat: &[u8]) -> Vec<PatternData> {
+    let m = pat.len();
+    let mut pattern_data: Vec<PatternData> = Vec::with_capacity(2);
+
+    let mut new_vec = pat.to_vec();
+    new_vec.push(0);
+    let new_pat = new_vec.as_slice();
+
+    let next_table = build_next_table(new_pat, m);
+
+    pattern_data.push(PatternData::PatternU8Vec(new_pat.to_owned()));
+    pattern_data.push(PatternData::PatternIntVec(next_table));
+
+    pattern_data
+}
+
+/*
+    Perform the KMP algorithm on the given pattern of length m, against the
+    sequence of length n, reporting match spans instead of a count.
+
+    `Standard` reports every occurrence exactly as `kmp` counts them,
+    including overlapping ones. The two leftmost kinds instead reset `i`
+    to 0 and continue from the match's end (where `j` already points), so
+    the next match found cannot overlap the one just reported.
+*/
+fn kmp_spans(pat_data: &[PatternData], sequence: &[u8], kind: MatchKind) -> Vec<MatchSpan> {
+    let mut i: i32 = 0;
+    let mut j: usize = 0;
+    let mut spans: Vec<MatchSpan> = Vec::new();
+
+    let pattern = match &pat_data[0] {
+        PatternData::PatternU8Vec(pat_as_vec) => pat_as_vec,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+    let next_table = match &pat_data[1] {
+        PatternData::PatternIntVec(table) => table,
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    };
+
+    let m = pattern.len() - 1;
+    let n = sequence.len();
+
+    while j < n {
+        while i > -1 && pattern[i as usize] != sequence[j] {
+            i = next_table[i as usize];
+        }
+        i += 1;
+        j += 1;
+        if i >= m as i32 {
+            spans.push(MatchSpan {
+                pattern: 0,
+                start: j - m,
+                end: j,
+            });
+            i = match kind {
+                MatchKind::Standard => next_table[i as usize],
+                MatchKind::LeftmostFirst | MatchKind::LeftmostLongest => 0,
+            };
+        }
+    }
+
+    spans
+}
+
+/*
+    All that is done here is call the run_spans() function with the argv
+    values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_spans(&init_kmp_spans, &kmp_spans, "kmp_spans", argv));
+}
@@ -0,0 +1,101 @@
+/*
+    Streaming variant of the Knuth-Morris-Pratt single-pattern matcher.
+
+    `kmp` requires the entire sequence to already be in memory as a
+    `&[u8]`. This variant instead reads the sequence from a file through a
+    `BufReader` in fixed-size chunks (see `common::run::run_stream`),
+    carrying the failure-function index `i` from one chunk to the next --
+    the sequence cursor `j` need not be carried, since each chunk is
+    indexed from its own start.
+
+    `build_next_table` is identical to `kmp`'s; see that crate for the
+    jump-table construction. `init_kmp_stream` matches `kmp`'s `init_kmp`
+    without the case folding: the pattern is used as given and no fold
+    table is packed.
+*/
+
+use common::run::{run_stream, PatternData};
+use std::env;
+use std::process::exit;
+
+fn build_next_table(pat: &[u8], m: usize) -> Vec<i32> {
+    let mut next_table: Vec<i32> = vec![0; m + 1];
+    let mut i: usize = 0;
+    let mut j: i32 = -1;
+    next_table[0] = -1;
+
+    while i < m {
+        while j > -1 && pat[i] != pat[j as usize] {
+            j = next_table[j as usize];
+        }
+        i += 1;
+        j += 1;
+        if i < m && pat[i] == pat[j as usize] {
+            next_table[i] = next_table[j as usize];
+        } else {
+            next_table[i] = j;
+        }
+    }
+
+    next_table
+}
+
+fn init_kmp_stream(pat: &[u8]) -> Vec<PatternData> {
+    let m = pat.len();
+    let mut pattern_data: Vec<PatternData> = Vec::with_capacity(2);
+
+    let mut new_vec = pat.to_vec();
+    new_vec.push(0);
+    let new_pat = new_vec.as_slice();
+
+    let next_table = build_next_table(new_pat, m);
+
+    pattern_data.push(PatternData::PatternU8Vec(new_pat.to_owned()));
+    pattern_data.push(PatternData::PatternIntVec(next_table));
+
+    pattern_data
+}
+
+/*
+    Perform the KMP algorithm against one chunk of the sequence, resuming
+    from `i_in` (the failure-function index left over from the previous
+    chunk, or 0 for the first) and returning the index to resume from on
+    the next chunk along with the match count found in this one.
+*/
+fn kmp_stream(pat_data: &[PatternData], chunk: &[u8], i_in: i32) -> (i32, u32) {
+    let mut i: i32 = i_in;
+    let mut matches: u32 = 0;
+
+    let pattern = match &pat_data[0] {
+        PatternData::PatternU8Vec(pat_as_vec) => pat_as_vec,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+    let next_table = match &pat_data[1] {
+        PatternData::PatternIntVec(table) => table,
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    };
+
+    let m = pattern.len() - 1;
+
+    for &byte in chunk {
+        while i > -1 && pattern[i as usize] != byte {
+            i = next_table[i as usize];
+        }
+        i += 1;
+        if i >= m as i32 {
+            matches += 1;
+            i = next_table[i as usize];
+        }
+    }
+
+    (i, matches)
+}
+
+/*
+    All that is done here is call the run_stream() function with the
+    argv values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_stream(&init_kmp_stream, &kmp_stream, "kmp_stream", argv));
+}
@@ -6,6 +6,7 @@
     Thierry Lecroq.
 */
 
+use common::alphabet::case_fold_table;
 use common::run::{run, PatternData};
 use std::env;
 use std::process::exit;
@@ -41,15 +42,24 @@ fn build_next_table(pat: &[u8], m: usize) -> Vec<i32> {
 /*
     Initialize the pattern for Knuth-Morris-Pratt and save the elements in the
     packed form for use with calls to `kmp`.
+
+    Case-insensitive matching is opt-in via the `CASE_INSENSITIVE`
+    environment variable (see `AUTOMATON_CACHE` in `common::run` for the
+    same opt-in-toggle convention). When it's set, the pattern is folded to
+    lowercase here, and the fold table is packed alongside it so `kmp` can
+    fold each sequence byte the same way before comparing; when it's not
+    set, the table is the identity mapping and folding is a no-op.
 */
 fn init_kmp(pat: &[u8]) -> Vec<PatternData> {
-    let m = pat.len();
-    let mut pattern_data: Vec<PatternData> = Vec::with_capacity(2);
+    let fold = case_fold_table(env::var("CASE_INSENSITIVE").is_ok());
+    let folded_pat: Vec<u8> = pat.iter().map(|&b| fold[b as usize]).collect();
+    let m = folded_pat.len();
+    let mut pattern_data: Vec<PatternData> = Vec::with_capacity(3);
 
     // Because the C code takes advantage of the presence of a null byte at the
     // end of strings, we have to force this in and re-convert the pattern to a
     // &[u8].
-    let mut new_vec = pat.to_vec();
+    let mut new_vec = folded_pat;
     new_vec.push(0);
     let new_pat = new_vec.as_slice();
 
@@ -58,6 +68,7 @@ fn init_kmp(pat: &[u8]) -> Vec<PatternData> {
 
     pattern_data.push(PatternData::PatternU8Vec(new_pat.to_owned()));
     pattern_data.push(PatternData::PatternIntVec(next_table));
+    pattern_data.push(PatternData::PatternFoldTable(fold));
 
     pattern_data
 }
@@ -81,6 +92,10 @@ fn kmp(pat_data: &[PatternData], sequence: &[u8]) -> i32 {
         PatternData::PatternIntVec(table) => table,
         _ => panic!("Incorrect value at pat_data slot 1"),
     };
+    let fold = match &pat_data[2] {
+        PatternData::PatternFoldTable(table) => table,
+        _ => panic!("Incorrect value at pat_data slot 2"),
+    };
 
     // Sizes of pattern and sequence. Account for the sentinel character added
     // to the pattern.
@@ -89,7 +104,7 @@ fn kmp(pat_data: &[PatternData], sequence: &[u8]) -> i32 {
 
     // The core algorithm.
     while j < n {
-        while i > -1 && pattern[i as usize] != sequence[j] {
+        while i > -1 && pattern[i as usize] != fold[sequence[j] as usize] {
             i = next_table[i as usize];
         }
         i += 1;
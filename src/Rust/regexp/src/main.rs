@@ -9,14 +9,24 @@ use std::env;
 use std::process::exit;
 use std::str;
 
-use pcre2::bytes::Regex;
+use pcre2::bytes::{CaptureLocations, Regex, RegexBuilder};
 
 // This declares a global value into which we can store the compiled regular
 // expression. This is because adding a reference to pcre2::bytes::Regex to
 // the `ApproxPatternData` enum would have required every one of the
 // experiments to be compiled against the PCRE2 library.
+//
+// Alongside the regex itself we keep a `CaptureLocations`, which owns the
+// match-data/JIT-stack block PCRE2 needs to run a search. Creating one of
+// these is the expensive part of a search call, so it's built once here and
+// reused across every sequence instead of being reacquired per match the way
+// `captures_iter` does internally.
 thread_local!(
-    static RE: RefCell<Regex> = RefCell::new(Regex::new("").unwrap())
+    static RE: RefCell<(Regex, CaptureLocations)> = RefCell::new({
+        let re = RegexBuilder::new().build("").unwrap();
+        let locs = re.capture_locations();
+        (re, locs)
+    })
 );
 
 /*
@@ -41,9 +51,18 @@ fn init_regexp(pattern: &[u8], k: u32) -> Vec<ApproxPatternData> {
 
     // Because adding the Regex type to the ApproxPattenData enum would require
     // the all the tools to link with the regex crate, here we're using a
-    // "trick" global approach.
+    // "trick" global approach. JIT-compile the pattern (falling back to the
+    // interpreter if the PCRE2 build doesn't support it) so that the reused
+    // match data below is run against machine code rather than re-interpreted
+    // on every call.
+    let re = RegexBuilder::new()
+        .jit_if_available(true)
+        .build(&built_re)
+        .unwrap();
+    let locs = re.capture_locations();
+
     RE.with(|val| {
-        *val.borrow_mut() = Regex::new(&built_re).unwrap();
+        *val.borrow_mut() = (re, locs);
     });
 
     pattern_data
@@ -53,15 +72,31 @@ fn init_regexp(pattern: &[u8], k: u32) -> Vec<ApproxPatternData> {
     Perform the regular expression variant matching on the given sequence.
 */
 fn regexp(_pat_data: &[ApproxPatternData], sequence: &[u8]) -> i32 {
-    let mut matches: usize = 0;
+    let mut matches: i32 = 0;
 
-    // Pull the pre-processed regex from the RE static global and apply it to
-    // `sequence`.
+    // Pull the pre-processed regex and its reused match data from the RE
+    // thread-local and apply it to `sequence`. The pattern is always wrapped
+    // in a zero-width look-ahead, so every match has `start() == end()`;
+    // advancing the search position by one past each hit (rather than past
+    // the, here nonexistent, matched text) is what allows overlapping
+    // candidates to be found.
     RE.with(|val| {
-        matches = val.borrow().captures_iter(sequence).count();
+        let (re, locs) = &mut *val.borrow_mut();
+        let n = sequence.len();
+        let mut start = 0;
+
+        while start <= n {
+            match re.captures_read_at(locs, sequence, start).unwrap() {
+                Some(found) => {
+                    matches += 1;
+                    start = found.end() + 1;
+                }
+                None => break,
+            }
+        }
     });
 
-    matches as i32
+    matches
 }
 
 /*
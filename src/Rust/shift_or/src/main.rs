@@ -1,12 +1,15 @@
 /*
-    Implementation of the Shift-Or algorithm.
+    Implementation of the Shift-Or algorithm, generalized to allow up to `k`
+    mismatches (the Wu-Manber bit-parallel recurrence for approximate
+    matching) and to patterns longer than one `WordType` word.
 
     This is based heavily on the C code given in chapter 5 of the book,
     "Handbook of Exact String-Matching Algorithms," by Christian Charras and
-    Thierry Lecroq.
+    Thierry Lecroq, extended with the approximate-matching recurrence from
+    Wu and Manber's "Fast Text Searching Allowing Errors."
 */
 
-use common::run::{run, PatternData, WordType};
+use common::run::{run_approx, ApproxPatternData, WordType};
 use std::env;
 use std::process::exit;
 
@@ -14,10 +17,9 @@ use std::process::exit;
 // are just using ASCII characters, so 128 is fine.
 const ASIZE: usize = 128;
 
-// We need to also know the word size in bits. For this, we're going to use
-// `u64` values. This allows a search pattern of up to 64 characters, even
-// though the experimental data doesn't go nearly this high. This is a sort of
-// "insurance" against adding other experiments that might push this limit.
+// The word size in bits of `WordType`. Patterns longer than this no longer
+// fit in a single state word; `init_shift_or` switches to the multi-word
+// representation once `m` exceeds it.
 const WORD: usize = 64;
 
 /*
@@ -25,6 +27,8 @@ const WORD: usize = 64;
     alphabet within the pattern `pat`. Unlike other algorithms' pre-processing,
     here it is necessary to pass s_positions[] in as a mutable parameter,
     because the algorithm needs this function to return the `limit` value.
+
+    This is the single-word fast path, used when `m <= WORD`.
 */
 fn calc_s_positions(
     pat: &[u8],
@@ -47,54 +51,180 @@ fn calc_s_positions(
 }
 
 /*
-    Initialize the pattern for Shift-Or. Here, that means getting the vector
-    `s_positions` set up and packing that along with `lim` into the data that
-    will get passed to `shift_or` for each sequence.
+    Multi-word counterpart to `calc_s_positions`, used when `m > WORD`. Each
+    of the `ASIZE` alphabet symbols gets a block vector of `ceil(m / WORD)`
+    words instead of a single word, with bit `i % WORD` of block `i / WORD` cleared
+    for every position `i` in `pat` where that symbol occurs. `lim` is still
+    a single word: only the top (most significant) block ever needs to be
+    tested against it, since a match can only be declared once bit `m - 1`
+    (which always lives in the top block) is clear.
 */
-fn init_shift_or(pat: &[u8]) -> Vec<PatternData> {
-    let mut pattern_data: Vec<PatternData> = Vec::with_capacity(2);
-    let mut s_positions: Vec<WordType> = vec![!0; ASIZE];
-    let m = pat.len();
+fn calc_s_positions_multi(
+    pat: &[u8],
+    m: usize,
+    num_blocks: usize,
+) -> (Vec<Vec<WordType>>, WordType) {
+    let mut s_positions: Vec<Vec<WordType>> = vec![vec![!0; num_blocks]; ASIZE];
+
+    for (i, &ch) in pat.iter().enumerate().take(m) {
+        let block = i / WORD;
+        let bit = i % WORD;
+        s_positions[ch as usize][block] &= !(1 << bit);
+    }
+
+    // `lim` only concerns the bit position of the final pattern character
+    // within the top block, so it's computed exactly as the single-word
+    // `calc_s_positions` would for a pattern whose length is the position of
+    // that final character within the top block.
+    let local_m = (m - 1) % WORD + 1;
+    let mut j: WordType = 1;
+    let mut lim: WordType = 0;
+    for _ in 0..local_m {
+        lim |= j;
+        j <<= 1;
+    }
+    lim = !(lim >> 1);
+
+    (s_positions, lim)
+}
+
+/*
+    Build the initial multi-word state block for error level `d`: the block-
+    wise equivalent of clearing the low `d` bits of an all-ones state. Blocks
+    entirely below bit `d` are cleared to all-zero, the block straddling bit
+    `d` has its low `d % WORD` bits cleared, and every block above that stays
+    all-ones.
+*/
+fn initial_state_block(d: usize, num_blocks: usize) -> Vec<WordType> {
+    let mut block = vec![!0; num_blocks];
+    let full_blocks = d / WORD;
+    let rem_bits = d % WORD;
+
+    for b in block.iter_mut().take(full_blocks) {
+        *b = 0;
+    }
+    if full_blocks < num_blocks {
+        block[full_blocks] = !(((1 as WordType) << rem_bits) - 1);
+    }
 
-    // Verify that the pattern is not too long:
-    if m > WORD {
-        panic!("shift_or: Pattern size must be <= {}", WORD);
+    block
+}
+
+/*
+    Shift a multi-word state block one bit to the left, carrying the top bit
+    of each word into the bottom bit of the next, more-significant word, then
+    OR in `add` (the per-character `S[c]` block for this byte). This is the
+    multi-word equivalent of `(state << 1) | s`.
+*/
+fn block_shl1_or(state: &[WordType], add: &[WordType]) -> Vec<WordType> {
+    let mut out = vec![0; state.len()];
+    let mut carry: WordType = 0;
+
+    for (i, word) in state.iter().enumerate() {
+        let top_bit = word >> (WORD - 1);
+        out[i] = (word << 1) | carry | add[i];
+        carry = top_bit;
     }
 
-    // Preprocessing. Set up s_positions and lim.
-    let lim: WordType = calc_s_positions(pat, m, &mut s_positions);
+    out
+}
+
+/*
+    Shift a multi-word state block one bit to the left, as above, without
+    ORing anything in. This is the multi-word equivalent of `state << 1`.
+*/
+fn block_shl1(state: &[WordType]) -> Vec<WordType> {
+    let mut out = vec![0; state.len()];
+    let mut carry: WordType = 0;
+
+    for (i, word) in state.iter().enumerate() {
+        let top_bit = word >> (WORD - 1);
+        out[i] = (word << 1) | carry;
+        carry = top_bit;
+    }
+
+    out
+}
+
+/*
+    Blockwise AND of two multi-word state blocks.
+*/
+fn block_and(a: &[WordType], b: &[WordType]) -> Vec<WordType> {
+    a.iter().zip(b.iter()).map(|(x, y)| x & y).collect()
+}
+
+/*
+    Initialize the pattern for Shift-Or. Here, that means getting the
+    `s_positions` table set up (single-word or multi-word, depending on `m`)
+    and packing that along with `lim` and `k` into the data that will get
+    passed to `shift_or` for each sequence.
+*/
+fn init_shift_or(pat: &[u8], k: u32) -> Vec<ApproxPatternData> {
+    let mut pattern_data: Vec<ApproxPatternData> = Vec::with_capacity(3);
+    let m = pat.len();
 
-    pattern_data.push(PatternData::PatternWord(lim));
-    pattern_data.push(PatternData::PatternWordVec(s_positions));
+    if m <= WORD {
+        // Single-word fast path: identical to the original implementation.
+        let mut s_positions: Vec<WordType> = vec![!0; ASIZE];
+        let lim: WordType = calc_s_positions(pat, m, &mut s_positions);
+
+        pattern_data.push(ApproxPatternData::PatternWord(lim));
+        pattern_data.push(ApproxPatternData::PatternWordVec(s_positions));
+    } else {
+        // Multi-word path for patterns longer than one word.
+        let num_blocks = m.div_ceil(WORD);
+        let (s_positions, lim) = calc_s_positions_multi(pat, m, num_blocks);
+
+        pattern_data.push(ApproxPatternData::PatternWord(lim));
+        pattern_data.push(ApproxPatternData::PatternWordVecVec(s_positions));
+    }
+    pattern_data.push(ApproxPatternData::PatternUsize(k as usize));
 
     pattern_data
 }
 
 /*
-    Perform the Shift-Or algorithm on the given pattern of length m, against
-    the sequence of length n.
+    Perform the single-word Shift-Or/Wu-Manber recurrence, for patterns of
+    `m <= WORD` characters. With `k == 0` only `r[0]` is updated, which is
+    the original Shift-Or step unchanged.
 */
-fn shift_or(pat_data: &[PatternData], sequence: &[u8]) -> i32 {
+fn shift_or_scalar(
+    lim: WordType,
+    s_positions: &[WordType],
+    k: usize,
+    sequence: &[u8],
+) -> i32 {
     let mut matches: i32 = 0;
-    let mut state: WordType = !0;
 
-    // Unpack pat_data:
-    let lim = match &pat_data[0] {
-        PatternData::PatternWord(val) => val,
-        _ => panic!("Incorrect value at pat_data slot 0"),
-    };
-    let s_positions = match &pat_data[1] {
-        PatternData::PatternWordVec(arr) => arr,
-        _ => panic!("Incorrect value at pat_data slot 1"),
-    };
+    // One state word per error level, 0..=k. Level `d`'s initial state has
+    // its low `d` bits cleared, representing `d` free leading insertions in
+    // the Wu-Manber recurrence; level 0 (exact matching) starts all-ones.
+    let mut r: Vec<WordType> = (0..=k)
+        .map(|d| !(((1 as WordType) << d) - 1))
+        .collect();
+
+    for &ch in sequence.iter() {
+        let s = s_positions[ch as usize];
+        // Snapshot this byte's starting state for every error level before
+        // any of them are overwritten, so that the terms that need the
+        // previous iteration's values (`old[d]` and `prev_old`) read those
+        // rather than values already advanced this iteration.
+        let old = r.clone();
 
-    // Sizes of the sequence.
-    let n = sequence.len();
+        r[0] = (r[0] << 1) | s;
+
+        for d in 1..=k {
+            let prev_old = old[d - 1];
+            // Substitution: (old[d] << 1) | s, matched against (prev_old <<
+            // 1). Insertion: r[d - 1] (already updated this iteration) << 1.
+            // Deletion: prev_old, unshifted.
+            r[d] = ((old[d] << 1) | s)
+                & (prev_old << 1)
+                & (r[d - 1] << 1)
+                & prev_old;
+        }
 
-    // Perform the search:
-    for j in 0..n {
-        state = (state << 1) | s_positions[sequence[j] as usize];
-        if state < *lim {
+        if r[k] < lim {
             matches += 1;
         }
     }
@@ -103,10 +233,85 @@ fn shift_or(pat_data: &[PatternData], sequence: &[u8]) -> i32 {
 }
 
 /*
-    All that is done here is call the run() function with a pointer to the
-    algorithm implementation, the label for the algorithm, and the argv values.
+    Perform the same Shift-Or/Wu-Manber recurrence as `shift_or_scalar`, but
+    over multi-word state blocks so that patterns longer than `WORD`
+    characters are supported. Only the top block of `r[k]` is ever compared
+    against `lim`, since that's the block holding the bit for the final
+    pattern position.
+*/
+fn shift_or_multi(
+    lim: WordType,
+    s_positions: &[Vec<WordType>],
+    k: usize,
+    sequence: &[u8],
+) -> i32 {
+    let mut matches: i32 = 0;
+    let num_blocks = s_positions[0].len();
+    let top_block = num_blocks - 1;
+
+    // One state block per error level, 0..=k; see `initial_state_block` for
+    // why level `d` isn't simply all-ones.
+    let mut r: Vec<Vec<WordType>> = (0..=k)
+        .map(|d| initial_state_block(d, num_blocks))
+        .collect();
+
+    for &ch in sequence.iter() {
+        let s = &s_positions[ch as usize];
+        let old = r.clone();
+
+        r[0] = block_shl1_or(&old[0], s);
+
+        for d in 1..=k {
+            let prev_old = &old[d - 1];
+            let substitution = block_shl1_or(&old[d], s);
+            let insertion = block_shl1(&r[d - 1]);
+            let deletion_shifted = block_shl1(prev_old);
+
+            let mut new_rd = block_and(&substitution, &deletion_shifted);
+            new_rd = block_and(&new_rd, &insertion);
+            new_rd = block_and(&new_rd, prev_old);
+            r[d] = new_rd;
+        }
+
+        if r[k][top_block] < lim {
+            matches += 1;
+        }
+    }
+
+    matches
+}
+
+/*
+    Unpack `pat_data` and dispatch to the single-word or multi-word
+    recurrence, based on which variant `s_positions` was packed as.
+*/
+fn shift_or(pat_data: &[ApproxPatternData], sequence: &[u8]) -> i32 {
+    let lim = match &pat_data[0] {
+        ApproxPatternData::PatternWord(val) => *val,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+    let k = match &pat_data[2] {
+        ApproxPatternData::PatternUsize(val) => *val,
+        _ => panic!("Incorrect value at pat_data slot 2"),
+    };
+
+    match &pat_data[1] {
+        ApproxPatternData::PatternWordVec(s_positions) => {
+            shift_or_scalar(lim, s_positions, k, sequence)
+        }
+        ApproxPatternData::PatternWordVecVec(s_positions) => {
+            shift_or_multi(lim, s_positions, k, sequence)
+        }
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    }
+}
+
+/*
+    All that is done here is call the run_approx() function with a pointer to
+    the algorithm implementation, the label for the algorithm, and the argv
+    values.
 */
 fn main() {
     let argv: Vec<String> = env::args().collect();
-    exit(run(&init_shift_or, &shift_or, "shift_or", argv));
+    exit(run_approx(&init_shift_or, &shift_or, "shift_or", argv));
 }
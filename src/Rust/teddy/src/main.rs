@@ -0,0 +1,493 @@
+/*
+    Implementation of a Teddy-style SIMD prefilter for multi-pattern
+    matching, modeled on the packed matcher used by the aho-corasick crate.
+
+    The idea: for a small fingerprint length `n` (1-3 bytes), build two
+    16-entry nibble tables per fingerprint position (`lo`/`hi`), where bit
+    `1 << idx` is set in `lo[byte & 0xF]` and `hi[byte >> 4]` whenever
+    pattern `idx`'s byte at that position has that nibble. At search time, a
+    16-byte block of the sequence is split into low/high nibbles and each is
+    looked up in its table (via `_mm_shuffle_epi8` where available, with a
+    plain array-indexed fallback elsewhere); ANDing the `lo`/`hi` results
+    together for a fingerprint position yields, per byte of input, the set
+    of patterns that could still match starting there. ANDing across all
+    fingerprint positions narrows that down to real candidates, which are
+    then verified in full.
+
+    This only handles up to 8 patterns (one bit per pattern in a `u8` mask)
+    each at least as long as the fingerprint; anything else falls back to an
+    Aho-Corasick automaton walked over the whole sequence (see
+    `automaton_scan`).
+*/
+
+use common::alphabet::ByteClasses;
+use common::run::{run_multi, MultiPatternData};
+use std::env;
+use std::process::exit;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+// One 8-bit lane per pattern, so at most 8 patterns can be packed into a
+// single candidate mask.
+const MAX_PATTERNS: usize = 8;
+
+// SSE2/SSSE3 registers are 16 bytes wide.
+const LANE: usize = 16;
+
+// Upper bound on the fingerprint length. Chosen per the design this is
+// modeled on, and narrowed further (see `init_teddy`) to the shortest
+// pattern in the set so that every pattern always has a byte at every
+// fingerprint position.
+const MAX_FINGERPRINT: usize = 3;
+
+/*
+    Scan `sequence[start..end]` a byte at a time, checking every pattern
+    at every position. This is how the tail of the sequence (too short for
+    a full 16-byte SIMD block) is handled in the main search; pattern sets
+    that don't fit the packed representation use `automaton_scan` instead.
+*/
+fn naive_scan(
+    patterns: &[Vec<u8>],
+    sequence: &[u8],
+    start: usize,
+    end: usize,
+    matches: &mut [u32],
+) {
+    for i in start..end {
+        for (idx, pattern) in patterns.iter().enumerate() {
+            let m = pattern.len();
+            if i + m <= sequence.len() && sequence[i..i + m] == pattern[..] {
+                matches[idx] += 1;
+            }
+        }
+    }
+}
+
+/*
+    Build the `lo`/`hi` nibble tables for one fingerprint position. Bit
+    `1 << idx` is set in `lo[byte & 0xF]` and in `hi[byte >> 4]` for the
+    byte at `pos` in pattern `idx`.
+*/
+fn build_nibble_tables(
+    patterns: &[Vec<u8>],
+    pos: usize,
+) -> ([u8; LANE], [u8; LANE]) {
+    let mut lo = [0u8; LANE];
+    let mut hi = [0u8; LANE];
+
+    for (idx, pattern) in patterns.iter().enumerate() {
+        let byte = pattern[pos];
+        lo[(byte & 0x0F) as usize] |= 1 << idx;
+        hi[(byte >> 4) as usize] |= 1 << idx;
+    }
+
+    (lo, hi)
+}
+
+/*
+    Classify one 16-byte block against a single fingerprint position's
+    tables, producing one candidate mask byte per input byte. This is the
+    portable (non-SIMD) version, used when the SSSE3 path isn't available.
+    The sub-block tail of the sequence is handled by `naive_scan`.
+*/
+fn classify_block_scalar(
+    block: &[u8],
+    lo: &[u8; LANE],
+    hi: &[u8; LANE],
+) -> [u8; LANE] {
+    let mut out = [0u8; LANE];
+
+    for (i, &byte) in block.iter().enumerate() {
+        out[i] =
+            lo[(byte & 0x0F) as usize] & hi[(byte >> 4) as usize];
+    }
+
+    out
+}
+
+/*
+    SIMD version of `classify_block_scalar`, using `_mm_shuffle_epi8` (PSHUFB)
+    to perform both nibble-table lookups for all 16 bytes of the block at
+    once. Safety: the caller must have confirmed SSSE3 is available (via
+    `is_x86_feature_detected!("ssse3")`) before calling this.
+*/
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn classify_block_simd(
+    block: &[u8; LANE],
+    lo: &[u8; LANE],
+    hi: &[u8; LANE],
+) -> [u8; LANE] {
+    let bytes = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+    let lo_table = _mm_loadu_si128(lo.as_ptr() as *const __m128i);
+    let hi_table = _mm_loadu_si128(hi.as_ptr() as *const __m128i);
+
+    let low_mask = _mm_set1_epi8(0x0F);
+    let lo_nibbles = _mm_and_si128(bytes, low_mask);
+    let hi_nibbles = _mm_and_si128(_mm_srli_epi16(bytes, 4), low_mask);
+
+    let lo_hits = _mm_shuffle_epi8(lo_table, lo_nibbles);
+    let hi_hits = _mm_shuffle_epi8(hi_table, hi_nibbles);
+    let candidates = _mm_and_si128(lo_hits, hi_hits);
+
+    let mut out = [0u8; LANE];
+    _mm_storeu_si128(out.as_mut_ptr() as *mut __m128i, candidates);
+
+    out
+}
+
+// The "fail" value used by the fallback automaton's goto function, same
+// convention as `aho_corasick`.
+const AUTOMATON_FAIL: i32 = -1;
+
+/*
+    A minimal Aho-Corasick automaton, built and walked only when the pattern
+    set doesn't fit Teddy's packed representation (see `init_teddy`). This
+    is the same trie/goto/failure-function construction `aho_corasick`
+    itself uses (see that crate for the algorithm this is taken from),
+    duplicated here rather than shared so that `teddy` stays a single,
+    self-contained binary; output sets are kept as plain `Vec<usize>`
+    rather than `aho_corasick`'s custom `Set` type, since `Set` can't share
+    a `MultiPatternData<T>` slot with the `Vec<u8>` patterns this crate
+    already packs under `T`.
+*/
+fn automaton_create_new_state(num_classes: usize) -> Vec<i32> {
+    vec![AUTOMATON_FAIL; num_classes]
+}
+
+fn automaton_enter_pattern(
+    new_state: &mut usize,
+    pat: &[u8],
+    idx: usize,
+    goto_fn: &mut Vec<Vec<i32>>,
+    output_fn: &mut Vec<Vec<usize>>,
+    classes: &ByteClasses,
+) {
+    let len = pat.len();
+    let mut j: usize = 0;
+    let mut state: usize = 0;
+
+    while j < len && goto_fn[state][classes.class(pat[j])] != AUTOMATON_FAIL {
+        state = goto_fn[state][classes.class(pat[j])] as usize;
+        j += 1;
+    }
+
+    for p in pat.iter().take(len).skip(j) {
+        *new_state += 1;
+        goto_fn[state][classes.class(*p)] = *new_state as i32;
+        state = *new_state;
+        goto_fn.push(automaton_create_new_state(classes.num_classes()));
+        output_fn.push(Vec::new());
+    }
+
+    output_fn[state].push(idx);
+}
+
+fn automaton_build_goto(
+    patterns: &[Vec<u8>],
+    goto_fn: &mut Vec<Vec<i32>>,
+    classes: &ByteClasses,
+) -> Vec<Vec<usize>> {
+    let mut new_state: usize = 0;
+    let mut output_fn: Vec<Vec<usize>> = Vec::new();
+
+    goto_fn.push(automaton_create_new_state(classes.num_classes()));
+    output_fn.push(Vec::new());
+
+    for (i, pattern) in patterns.iter().enumerate() {
+        automaton_enter_pattern(&mut new_state, pattern, i, goto_fn, &mut output_fn, classes);
+    }
+
+    for cl in 0..classes.num_classes() {
+        if goto_fn[0][cl] == AUTOMATON_FAIL {
+            goto_fn[0][cl] = 0;
+        }
+    }
+
+    output_fn
+}
+
+fn automaton_build_failure(
+    goto_fn: &[Vec<i32>],
+    output_fn: &mut [Vec<usize>],
+    classes: &ByteClasses,
+) -> Vec<usize> {
+    let mut queue: std::collections::VecDeque<usize> = std::collections::VecDeque::new();
+    let mut failure_fn: Vec<usize> = vec![0; goto_fn.len()];
+
+    for cl in 0..classes.num_classes() {
+        let state = goto_fn[0][cl];
+        if state == 0 {
+            continue;
+        }
+
+        queue.push_back(state as usize);
+        failure_fn[state as usize] = 0;
+    }
+
+    while let Some(r) = queue.pop_front() {
+        for a in 0..classes.num_classes() {
+            let s = goto_fn[r][a];
+            if s == AUTOMATON_FAIL {
+                continue;
+            }
+            let ss = s as usize;
+
+            queue.push_back(ss);
+            let mut state = failure_fn[r];
+            while goto_fn[state][a] == AUTOMATON_FAIL {
+                state = failure_fn[state];
+            }
+            failure_fn[ss] = goto_fn[state][a] as usize;
+            let failure_set = output_fn[failure_fn[ss]].clone();
+            for j in failure_set {
+                if !output_fn[ss].contains(&j) {
+                    output_fn[ss].push(j);
+                }
+            }
+        }
+    }
+
+    failure_fn
+}
+
+/*
+    Walk the fallback automaton across the whole sequence in one pass,
+    incrementing every pattern reached at each state -- this is exactly
+    `aho_corasick`'s search step, used here as the "falls back to
+    `aho_corasick`" path for pattern sets Teddy's packed representation
+    can't hold.
+*/
+fn automaton_scan(
+    goto_fn: &[Vec<i32>],
+    failure_fn: &[usize],
+    output_fn: &[Vec<usize>],
+    table: &[u8],
+    sequence: &[u8],
+    matches: &mut [u32],
+) {
+    let mut state: usize = 0;
+
+    for &s in sequence.iter() {
+        let cl = table[s as usize] as usize;
+        while goto_fn[state][cl] == AUTOMATON_FAIL {
+            state = failure_fn[state];
+        }
+
+        state = goto_fn[state][cl] as usize;
+        for &j in output_fn[state].iter() {
+            matches[j] += 1;
+        }
+    }
+}
+
+/*
+    Initialize the Teddy prefilter. When the pattern set fits (at most
+    `MAX_PATTERNS` patterns, all at least one byte long), this picks the
+    fingerprint length, builds the nibble tables for each fingerprint
+    position, and packs them along with the patterns themselves (needed for
+    verification). Otherwise, a full Aho-Corasick automaton is built instead
+    and `teddy` falls back to walking it (see `automaton_scan`) for the
+    whole sequence.
+*/
+fn init_teddy(patterns: &[&[u8]]) -> Vec<MultiPatternData<Vec<u8>>> {
+    let mut pattern_data: Vec<MultiPatternData<Vec<u8>>> =
+        Vec::with_capacity(8);
+    let owned_patterns: Vec<Vec<u8>> =
+        patterns.iter().map(|p| p.to_vec()).collect();
+    let min_len = owned_patterns.iter().map(|p| p.len()).min().unwrap_or(0);
+
+    pattern_data.push(MultiPatternData::PatternCount(owned_patterns.len()));
+
+    if owned_patterns.len() <= MAX_PATTERNS && min_len >= 1 {
+        let n = MAX_FINGERPRINT.min(min_len);
+        let mut tables: Vec<Vec<i32>> = Vec::with_capacity(2 * n);
+
+        for pos in 0..n {
+            let (lo, hi) = build_nibble_tables(&owned_patterns, pos);
+            tables.push(lo.iter().map(|&b| b as i32).collect());
+            tables.push(hi.iter().map(|&b| b as i32).collect());
+        }
+
+        pattern_data.push(MultiPatternData::PatternUsizeVec(vec![n]));
+        pattern_data.push(MultiPatternData::PatternIntVecVec(tables));
+        pattern_data.push(MultiPatternData::PatternTypeVec(owned_patterns));
+        pattern_data.push(MultiPatternData::PatternIntVecVec(vec![]));
+        pattern_data.push(MultiPatternData::PatternUsizeVec(vec![]));
+        pattern_data.push(MultiPatternData::PatternUsizeVecVec(vec![]));
+        pattern_data.push(MultiPatternData::PatternByteClasses(vec![], 0));
+    } else {
+        let classes = ByteClasses::from_patterns(patterns);
+        let mut goto_fn: Vec<Vec<i32>> = Vec::new();
+        let mut output_fn = automaton_build_goto(&owned_patterns, &mut goto_fn, &classes);
+        let failure_fn = automaton_build_failure(&goto_fn, &mut output_fn, &classes);
+
+        pattern_data.push(MultiPatternData::PatternUsizeVec(vec![]));
+        pattern_data.push(MultiPatternData::PatternIntVecVec(vec![]));
+        pattern_data.push(MultiPatternData::PatternTypeVec(owned_patterns));
+        pattern_data.push(MultiPatternData::PatternIntVecVec(goto_fn));
+        pattern_data.push(MultiPatternData::PatternUsizeVec(failure_fn));
+        pattern_data.push(MultiPatternData::PatternUsizeVecVec(output_fn));
+        pattern_data.push(MultiPatternData::PatternByteClasses(
+            classes.table(),
+            classes.num_classes(),
+        ));
+    }
+
+    pattern_data
+}
+
+/*
+    Perform the Teddy prefilter search. For each 16-byte block of the
+    sequence with enough trailing bytes for a full fingerprint, every
+    fingerprint position's candidate mask is computed (via SIMD when SSSE3
+    is available, scalar otherwise) and ANDed together; any input byte left
+    with a non-zero mask is a candidate start position, verified in full
+    against every pattern whose bit is set. The tail too short for a full
+    block is handled by `naive_scan`; pattern sets that didn't fit the
+    packed representation at all are handled by the fallback automaton (see
+    `automaton_scan`) instead.
+*/
+fn teddy(
+    pat_data: &[MultiPatternData<Vec<u8>>],
+    sequence: &[u8],
+) -> Vec<u32> {
+    let pattern_count = match &pat_data[0] {
+        MultiPatternData::PatternCount(val) => *val,
+        _ => panic!("Incorrect value at pat_data slot 0"),
+    };
+    let n_vec = match &pat_data[1] {
+        MultiPatternData::PatternUsizeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 1"),
+    };
+    let tables = match &pat_data[2] {
+        MultiPatternData::PatternIntVecVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 2"),
+    };
+    let patterns = match &pat_data[3] {
+        MultiPatternData::PatternTypeVec(val) => val,
+        _ => panic!("Incorrect value at pat_data slot 3"),
+    };
+
+    let mut matches: Vec<u32> = vec![0; pattern_count];
+    let n = sequence.len();
+
+    // Pattern set didn't fit the packed representation: fall back to the
+    // full Aho-Corasick automaton built for this case in `init_teddy`.
+    if n_vec.is_empty() {
+        let goto_fn = match &pat_data[4] {
+            MultiPatternData::PatternIntVecVec(val) => val,
+            _ => panic!("Incorrect value at pat_data slot 4"),
+        };
+        let failure_fn = match &pat_data[5] {
+            MultiPatternData::PatternUsizeVec(val) => val,
+            _ => panic!("Incorrect value at pat_data slot 5"),
+        };
+        let output_fn = match &pat_data[6] {
+            MultiPatternData::PatternUsizeVecVec(val) => val,
+            _ => panic!("Incorrect value at pat_data slot 6"),
+        };
+        let classes_table = match &pat_data[7] {
+            MultiPatternData::PatternByteClasses(table, _) => table,
+            _ => panic!("Incorrect value at pat_data slot 7"),
+        };
+
+        automaton_scan(goto_fn, failure_fn, output_fn, classes_table, sequence, &mut matches);
+        return matches;
+    }
+
+    let fp_len = n_vec[0];
+    let lo_tables: Vec<[u8; LANE]> = (0..fp_len)
+        .map(|p| {
+            let mut t = [0u8; LANE];
+            t.copy_from_slice(
+                &tables[2 * p].iter().map(|&v| v as u8).collect::<Vec<u8>>(),
+            );
+            t
+        })
+        .collect();
+    let hi_tables: Vec<[u8; LANE]> = (0..fp_len)
+        .map(|p| {
+            let mut t = [0u8; LANE];
+            t.copy_from_slice(
+                &tables[2 * p + 1]
+                    .iter()
+                    .map(|&v| v as u8)
+                    .collect::<Vec<u8>>(),
+            );
+            t
+        })
+        .collect();
+
+    #[cfg(target_arch = "x86_64")]
+    let use_simd = is_x86_feature_detected!("ssse3");
+    #[cfg(not(target_arch = "x86_64"))]
+    let use_simd = false;
+
+    // Process full blocks: a block starting at `base` needs bytes up
+    // through `base + LANE - 1 + (fp_len - 1)` to test every lane's
+    // fingerprint, so stop once that would run past the end of `sequence`.
+    let mut base = 0;
+    while base + LANE + fp_len - 1 <= n {
+        let mut combined = [0xFFu8; LANE];
+
+        for p in 0..fp_len {
+            let block = &sequence[base + p..base + p + LANE];
+
+            let classified = if use_simd {
+                #[cfg(target_arch = "x86_64")]
+                {
+                    let mut arr = [0u8; LANE];
+                    arr.copy_from_slice(block);
+                    unsafe {
+                        classify_block_simd(&arr, &lo_tables[p], &hi_tables[p])
+                    }
+                }
+                #[cfg(not(target_arch = "x86_64"))]
+                {
+                    classify_block_scalar(block, &lo_tables[p], &hi_tables[p])
+                }
+            } else {
+                classify_block_scalar(block, &lo_tables[p], &hi_tables[p])
+            };
+
+            for j in 0..LANE {
+                combined[j] &= classified[j];
+            }
+        }
+
+        for (j, &mask) in combined.iter().enumerate() {
+            if mask == 0 {
+                continue;
+            }
+            let start = base + j;
+            for idx in 0..pattern_count {
+                if mask & (1 << idx) == 0 {
+                    continue;
+                }
+                let m = patterns[idx].len();
+                if start + m <= n && sequence[start..start + m] == patterns[idx][..]
+                {
+                    matches[idx] += 1;
+                }
+            }
+        }
+
+        base += LANE;
+    }
+
+    // Tail too short for a full SIMD block: fall back to a direct scan.
+    naive_scan(patterns, sequence, base, n, &mut matches);
+
+    matches
+}
+
+/*
+    All that is done here is call the run_multi() function with the argv
+    values.
+*/
+fn main() {
+    let argv: Vec<String> = env::args().collect();
+    exit(run_multi(&init_teddy, &teddy, "teddy", argv));
+}